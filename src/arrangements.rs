@@ -0,0 +1,110 @@
+use std::iter;
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use super::count::binomial;
+use super::permutations_ms::MultisetPermutations;
+
+/// Add `ones` one digits to a collection of digits.
+fn with_ones(digits: &[u32], ones: usize) -> Vec<u32> {
+    digits
+        .iter()
+        .cloned()
+        .chain(iter::repeat_n(1, ones))
+        .collect()
+}
+
+/// Return the number of distinct orderings of a collection of digits.
+fn count_orderings(digits: &[u32]) -> BigUint {
+    let mut sorted = digits.to_vec();
+    sorted.sort();
+    let mut remaining = sorted.len();
+    let mut count = BigUint::one();
+    for run in sorted.chunk_by(|a, b| a == b) {
+        count *= binomial(remaining, run.len());
+        remaining -= run.len();
+    }
+    count
+}
+
+/// Iterate over the distinct arrangements of `digits`, with up to `max_ones` extra one
+/// digits inserted, skipping those with a leading zero (other than zero itself).
+///
+/// For integers of more than one digit, every arrangement has the same digit product,
+/// and so the same multiplicative persistence, as the original.
+pub fn arrangements(digits: &[u32], max_ones: usize) -> impl Iterator<Item = Vec<u32>> {
+    let digits = digits.to_vec();
+    (0..=max_ones)
+        .flat_map(move |ones| MultisetPermutations::new(with_ones(&digits, ones)))
+        .filter(|arrangement| arrangement.len() == 1 || arrangement[0] != 0)
+}
+
+/// Count the arrangements yielded by `arrangements`, without enumerating them.
+pub fn count_arrangements(digits: &[u32], max_ones: usize) -> BigUint {
+    let mut count = BigUint::zero();
+    for ones in 0..=max_ones {
+        let mut pool = with_ones(digits, ones);
+        if pool.is_empty() {
+            continue;
+        }
+        count += count_orderings(&pool);
+
+        // Remove the orderings starting with a zero, unless zero is the only digit
+        if let Some(zero) = pool.iter().position(|d| *d == 0).filter(|_| pool.len() > 1) {
+            pool.remove(zero);
+            count -= count_orderings(&pool);
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrangements() {
+        let results: Vec<Vec<u32>> = arrangements(&[7, 7, 2], 0).collect();
+        assert_eq!(results, vec![vec![2, 7, 7], vec![7, 2, 7], vec![7, 7, 2]]);
+
+        let results: Vec<Vec<u32>> = arrangements(&[2, 0], 1).collect();
+        assert_eq!(
+            results,
+            vec![
+                vec![2, 0],
+                vec![1, 0, 2],
+                vec![1, 2, 0],
+                vec![2, 0, 1],
+                vec![2, 1, 0]
+            ]
+        );
+
+        let results: Vec<Vec<u32>> = arrangements(&[0], 1).collect();
+        assert_eq!(results, vec![vec![0], vec![1, 0]]);
+        let results: Vec<Vec<u32>> = arrangements(&[1, 0], 0).collect();
+        assert_eq!(results, vec![vec![1, 0]]);
+    }
+
+    #[test]
+    fn test_count_arrangements() {
+        assert_eq!(count_arrangements(&[0], 0), BigUint::one());
+        assert_eq!(count_arrangements(&[1, 0], 0), BigUint::one());
+        let cases = [
+            vec![7, 7, 2],
+            vec![2, 0],
+            vec![0, 0, 3, 3, 9],
+            vec![0],
+            vec![1, 0],
+            vec![],
+        ];
+        for digits in cases.iter() {
+            for max_ones in 0..3 {
+                assert_eq!(
+                    count_arrangements(digits, max_ones),
+                    BigUint::from(arrangements(digits, max_ones).count())
+                );
+            }
+        }
+    }
+}
@@ -1,69 +1,34 @@
-extern crate cairo;
 extern crate clap;
 
-use std::f64::consts::PI;
+extern crate multiplicative_persistence;
+
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, BufReader};
 
-use cairo::{Context, Format, ImageSurface};
 use clap::{App, Arg};
 
-#[derive(Debug, Copy, Clone)]
-struct Options {
-    canvas_height: i32,
-    canvas_width: i32,
-    dot_radius: f64,
-    mp_filter_low: f64,
-    spacing_angular: f64,
-    spacing_radial: f64,
-}
-
-fn visualize(output_path: &str, options: &Options) {
-    let Options {
-        canvas_height,
-        canvas_width,
-        mp_filter_low,
-        dot_radius,
-        spacing_angular,
-        spacing_radial,
-    } = options;
-    let surface = ImageSurface::create(Format::ARgb32, *canvas_width, *canvas_height)
-        .expect("Couldn't create surface");
-    let context = Context::new(&surface);
-
-    // paint canvas white
-    context.set_source_rgb(1.0, 1.0, 1.0);
-    context.paint();
-    context.set_source_rgb(0.0, 0.0, 0.0);
+use multiplicative_persistence::table::Table;
+use multiplicative_persistence::visualize::{visualize, VisualizeOptions};
 
-    // draw line chart
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
+/// Read `(integer, persistence)` pairs from the text output of `mpersist list` on stdin.
+fn read_stdin() -> impl Iterator<Item = (f64, f64)> {
+    BufReader::new(io::stdin()).lines().map(|line| {
         let data = line.unwrap();
-        let split: Vec<&str> = data.split(" ").collect();
+        let split: Vec<&str> = data.split(' ').collect();
         let candidate: f64 = split[0].parse::<f64>().unwrap();
-        let mut mp: f64 = split[1].parse::<f64>().unwrap();
-
-        if mp <= *mp_filter_low {
-            mp = 0.0;
-        }
-
-        let n = candidate * spacing_angular;
-        let r = n.sqrt();
-        let t = 2.0 * PI * r;
-        let x = (*canvas_width as f64 * 0.5) + (r * t.cos()) * spacing_radial;
-        let y = (*canvas_height as f64 * 0.5) - (r * t.sin()) * spacing_radial;
-        let s = mp * dot_radius;
-        context.move_to(x, y);
-        context.arc(x, y, s, 0.0, 2.0 * PI);
-        context.fill();
-    }
-    context.stroke();
+        let mp: f64 = split[1].parse::<f64>().unwrap();
+        (candidate, mp)
+    })
+}
 
+/// Render the points to a PNG file.
+fn write_png<I: Iterator<Item = (f64, f64)>>(
+    points: I,
+    output_path: &str,
+    options: &VisualizeOptions,
+) {
     let mut file = File::create(output_path).expect("Couldn't create file");
-    surface
-        .write_to_png(&mut file)
-        .expect("Couldn't write to png");
+    visualize(points, options, &mut file).expect("Couldn't write to png");
 }
 
 pub fn main() {
@@ -75,6 +40,13 @@ pub fn main() {
                 .index(1)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("binary")
+                .help("Read a binary persistence table from this file instead of stdin")
+                .takes_value(true)
+                .short("b")
+                .long("binary"),
+        )
         .arg(
             Arg::with_name("width")
                 .help("canvas width")
@@ -119,7 +91,7 @@ pub fn main() {
         );
     let matches = app.get_matches();
     let output_path = matches.value_of("output_path").unwrap();
-    let options = Options {
+    let options = VisualizeOptions {
         canvas_height: matches
             .value_of("height")
             .unwrap_or("600")
@@ -151,5 +123,14 @@ pub fn main() {
             .parse()
             .expect("Invalid float for radial spacing"),
     };
-    visualize(output_path, &options);
+    match matches.value_of("binary") {
+        Some(table_path) => {
+            let table = Table::open(table_path).expect("Couldn't read persistence table");
+            let points = table
+                .iter()
+                .map(|(candidate, mp)| (candidate as f64, mp as f64));
+            write_png(points, output_path, &options);
+        }
+        None => write_png(read_stdin(), output_path, &options),
+    }
 }
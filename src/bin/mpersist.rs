@@ -2,63 +2,394 @@ extern crate clap;
 #[macro_use]
 extern crate log;
 extern crate env_logger;
+extern crate num_bigint;
 extern crate num_cpus;
+extern crate num_traits;
 extern crate threadpool;
 
 extern crate multiplicative_persistence;
 
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::TcpListener;
+use std::ops::RangeInclusive;
+use std::process;
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use clap::{App, Arg, SubCommand};
+use num_bigint::BigUint;
+use num_traits::{Num, One, Pow, ToPrimitive};
 use threadpool::ThreadPool;
 
-use multiplicative_persistence::{multiplicative_persistence, SearchResult, SearchRound};
+use multiplicative_persistence::arrangements::{arrangements, count_arrangements};
+use multiplicative_persistence::count::persistence_counts;
+use multiplicative_persistence::database::ResultsDatabase;
+use multiplicative_persistence::distributed::{coordinate, work, work_units};
+use multiplicative_persistence::explain::explain;
+use multiplicative_persistence::expression::evaluate;
+use multiplicative_persistence::multibase::{MultibaseRecord, MultibaseResult, MultibaseSearch};
+use multiplicative_persistence::numeral::{
+    Bijective, Factorial, Negabase, NumeralSystem, Standard,
+};
+use multiplicative_persistence::oeis::{write_b_file, OeisSequence};
+use multiplicative_persistence::preimage::smallest_preimage;
+use multiplicative_persistence::prime::{is_prime, SmallestPrimes};
+use multiplicative_persistence::range::PersistenceRange;
+use multiplicative_persistence::records::known_records;
+use multiplicative_persistence::sample::{sample_persistence, Rng, SampleMode};
+use multiplicative_persistence::sequence::{sequence_persistence, Sequence, SequenceResult};
+use multiplicative_persistence::server::Server;
+use multiplicative_persistence::smooth::smooth_search;
+use multiplicative_persistence::stats::PersistenceStats;
+use multiplicative_persistence::table::{Header, Table, TableWriter};
+use multiplicative_persistence::tree::{preimage_tree, to_dot, to_json};
+use multiplicative_persistence::verify::{parse_radix, verify_claims, Failure, ResultsParser};
+use multiplicative_persistence::{
+    from_digits, from_notation, multiplicative_persistence_in, multiplicative_persistence_uint,
+    to_digits, to_notation, CandidateDigits, SearchResult, SearchRound,
+};
+
+/// A message from a search round to the main thread.
+pub enum RoundMessage {
+    Result(SearchResult),
+    // The round is finished, taking this long unless it was read from the database
+    Complete(Option<u64>),
+}
 
 /// Consume a single search round, reporting results to the main thread.
-pub fn search_round(tx: Sender<SearchResult>, n: usize) -> () {
+pub fn search_round(tx: Sender<RoundMessage>, n: usize, digits: &CandidateDigits) {
     let round_start = Instant::now();
-    for result in SearchRound::new(n) {
-        tx.send(result).expect("Failed to send SearchResult");
+    for result in SearchRound::new(n, digits) {
+        tx.send(RoundMessage::Result(result))
+            .expect("Failed to send SearchResult");
     }
-    info!(
-        "info: round {} complete in {}ms",
-        n,
-        round_start.elapsed().as_millis()
-    );
+    let milliseconds = round_start.elapsed().as_millis() as u64;
+    info!("info: round {} complete in {}ms", n, milliseconds);
+    tx.send(RoundMessage::Complete(Some(milliseconds)))
+        .expect("Failed to send round completion");
 }
 
-/// Multithreaded search for integers with higher multiplicative persistence values.
-fn search(from_round: usize, num_rounds: usize, n_workers: usize) -> () {
+/// Each search round, with the channel its results arrive on.
+type RoundReceivers = Vec<(usize, Receiver<RoundMessage>)>;
+
+/// Multithreaded search for integers with higher multiplicative persistence values,
+/// in each radix given. Radices are reported in order, but share the same workers.
+///
+/// With a database, rounds it has completed are replayed rather than searched again,
+/// and newly completed rounds are recorded.
+fn search(
+    from_round: usize,
+    num_rounds: usize,
+    n_workers: usize,
+    radices: &[CandidateDigits],
+    mut database: Option<&mut (ResultsDatabase, i64)>,
+) {
     let pool = ThreadPool::new(n_workers);
-    let mut receivers: Vec<Receiver<SearchResult>> = Vec::new();
+    let mut radix_receivers: Vec<(CandidateDigits, RoundReceivers)> = Vec::new();
 
-    for n in from_round..(from_round + num_rounds) {
-        let (tx, rx): (Sender<SearchResult>, Receiver<SearchResult>) = channel();
-        pool.execute(move || search_round(tx, n));
-        receivers.push(rx);
+    for digits in radices.iter() {
+        let mut receivers: RoundReceivers = Vec::new();
+        for n in from_round..(from_round + num_rounds) {
+            let (tx, rx): (Sender<RoundMessage>, Receiver<RoundMessage>) = channel();
+            let completed = match &database {
+                Some((database, _)) => database
+                    .completed_round(digits, n)
+                    .expect("Failed to read database"),
+                None => None,
+            };
+            match completed {
+                Some(results) => {
+                    info!("info: round {} read from database", n);
+                    for (candidate, multiplicative_persistence) in results {
+                        tx.send(RoundMessage::Result(SearchResult {
+                            candidate: to_notation(&candidate, digits.radix),
+                            multiplicative_persistence,
+                        }))
+                        .expect("Failed to send SearchResult");
+                    }
+                    tx.send(RoundMessage::Complete(None))
+                        .expect("Failed to send round completion");
+                }
+                None => {
+                    let digits = digits.clone();
+                    pool.execute(move || search_round(tx, n, &digits));
+                }
+            }
+            receivers.push((n, rx));
+        }
+        radix_receivers.push((digits.clone(), receivers));
     }
 
-    let mut current_max = 2;
-    // Read results from our workers in order
-    for rx in receivers {
-        loop {
-            match rx.recv() {
-                // Validate this worker's result with parent state
-                Ok(SearchResult {
-                    candidate,
-                    multiplicative_persistence,
-                }) => {
-                    if multiplicative_persistence > current_max {
-                        current_max += 1;
-                        println!("{} {}", multiplicative_persistence, candidate);
+    let mut printer = RecordPrinter::new(radices.len() > 1, from_round);
+    for (digits, receivers) in radix_receivers {
+        // Read results from our workers in order
+        for (n, rx) in receivers {
+            let mut round_results: Vec<(BigUint, usize)> = Vec::new();
+            while let Ok(message) = rx.recv() {
+                let result = match message {
+                    RoundMessage::Result(result) => result,
+                    RoundMessage::Complete(milliseconds) => {
+                        if let (Some((database, run_id)), Some(milliseconds)) =
+                            (database.as_mut(), milliseconds)
+                        {
+                            database
+                                .complete_round(*run_id, &digits, n, milliseconds, &round_results)
+                                .expect("Failed to write database");
+                        }
+                        break;
                     }
+                };
+                let decimal = printer.print(&digits, &result);
+                round_results.push((decimal, result.multiplicative_persistence));
+            }
+        }
+    }
+}
+
+/// Prints search results that improve on every earlier result in their radix, in the
+/// order they are given.
+struct RecordPrinter {
+    multiple_radices: bool,
+    from_round: usize,
+    radix: u32,
+    current_max: usize,
+}
+
+impl RecordPrinter {
+    fn new(multiple_radices: bool, from_round: usize) -> RecordPrinter {
+        RecordPrinter {
+            multiple_radices,
+            from_round,
+            radix: 0,
+            current_max: 2,
+        }
+    }
+
+    /// Print a result if it is a record, returning the candidate in decimal.
+    fn print(&mut self, digits: &CandidateDigits, result: &SearchResult) -> BigUint {
+        let radix = digits.radix;
+        if radix != self.radix {
+            self.radix = radix;
+            self.current_max = 2;
+        }
+        let decimal: BigUint = Num::from_str_radix(&result.candidate, radix)
+            .expect("Could not convert candidate to BigUint");
+        let multiplicative_persistence = result.multiplicative_persistence;
+        if multiplicative_persistence <= self.current_max {
+            return decimal;
+        }
+        self.current_max = multiplicative_persistence;
+        if self.multiple_radices {
+            println!(
+                "{} {} {} {}",
+                radix, multiplicative_persistence, decimal, result.candidate
+            );
+        } else if radix != 10 {
+            println!(
+                "{} {} {}",
+                multiplicative_persistence, decimal, result.candidate
+            );
+        } else {
+            println!("{} {}", multiplicative_persistence, result.candidate);
+        }
+        // Records should match the known records, unless the search skips any candidates
+        let exhaustive = self.from_round == 0 && *digits == CandidateDigits::for_radix(radix);
+        if let Some(message) = known_records(radix)
+            .and_then(|known| known.contradiction(&decimal, multiplicative_persistence, exhaustive))
+        {
+            eprintln!("warning: {}", message);
+        }
+        decimal
+    }
+}
+
+/// Open the database given on the command line, if any, and record the start of a run.
+fn open_database(
+    matches: &clap::ArgMatches,
+    operation: &str,
+    radix: u32,
+) -> Option<(ResultsDatabase, i64)> {
+    matches.value_of("database").map(|path| {
+        let database = ResultsDatabase::open(path).expect("Couldn't open database");
+        let arguments: Vec<String> = std::env::args().collect();
+        let run_id = database
+            .start_run(operation, radix, &arguments.join(" "))
+            .expect("Failed to write database");
+        (database, run_id)
+    })
+}
+
+/// How to read, score and write each integer given to `for`.
+#[derive(Debug, Clone)]
+struct ForOptions {
+    radix: u32,
+    native: bool,
+    system: Option<String>,
+    format: String,
+}
+
+impl ForOptions {
+    fn evaluate(&self, candidate: &str) -> Result<(BigUint, usize), String> {
+        let candidate = candidate.trim();
+        let value = if self.native {
+            from_notation(candidate, self.radix)
+                .ok_or_else(|| format!("Invalid integer in radix {}", self.radix))?
+        } else {
+            evaluate(candidate).map_err(|error| error.to_string())?
+        };
+        let persistence = match &self.system {
+            Some(system) => {
+                multiplicative_persistence_in(&value, numeral_system(system, self.radix).as_ref())
+                    .ok_or_else(|| LOOP_MESSAGE.to_owned())?
+            }
+            None => multiplicative_persistence_uint(&value, self.radix),
+        };
+        Ok((value, persistence))
+    }
+
+    fn format(&self, candidate: &BigUint, persistence: usize) -> String {
+        match self.format.as_str() {
+            "csv" => format!("{},{}", candidate, persistence),
+            "jsonl" => format!(
+                "{{\"candidate\":\"{}\",\"persistence\":{}}}",
+                candidate, persistence
+            ),
+            _ => format!("{} {}", candidate, persistence),
+        }
+    }
+}
+
+/// Evaluate labelled candidates in parallel, writing results in order. Invalid candidates
+/// are reported on stderr by label, and we return whether all were valid.
+fn evaluate_batch<I: Iterator<Item = (String, String)>>(
+    candidates: I,
+    options: &ForOptions,
+    n_workers: usize,
+) -> bool {
+    let pool = ThreadPool::new(n_workers);
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    if options.format == "csv" {
+        writeln!(out, "candidate,persistence").expect("Failed to write output");
+    }
+
+    let mut valid = true;
+    let mut candidates = candidates.peekable();
+    while candidates.peek().is_some() {
+        // Evaluate a chunk at a time, so results stream out as input arrives
+        let mut receivers = Vec::new();
+        for (label, candidate) in candidates.by_ref().take(n_workers * 64) {
+            let (tx, rx) = channel();
+            let options = options.clone();
+            pool.execute(move || {
+                tx.send(options.evaluate(&candidate))
+                    .expect("Failed to send result");
+            });
+            receivers.push((label, rx));
+        }
+        for (label, rx) in receivers {
+            match rx.recv().expect("Failed to receive result") {
+                Ok((candidate, persistence)) => {
+                    writeln!(out, "{}", options.format(&candidate, persistence))
+                        .expect("Failed to write output");
+                }
+                Err(error) => {
+                    out.flush().expect("Failed to write output");
+                    eprintln!("{}: {}", label, error);
+                    valid = false;
                 }
-                // As soon as we get something other than a result
-                // this worker is complete, move on
-                _ => break,
             }
         }
+        out.flush().expect("Failed to write output");
+    }
+    valid
+}
+
+/// Gather persistence statistics over a range, split between a pool of workers.
+fn range_stats(from: u64, to: u64, radix: u32, n_workers: usize) -> PersistenceStats {
+    let pool = ThreadPool::new(n_workers);
+    let (tx, rx): (Sender<PersistenceStats>, Receiver<PersistenceStats>) = channel();
+
+    // Keep chunks large, as each range builds its own lookup tables
+    let chunk_size = (to.saturating_sub(from) / (n_workers as u64 * 4)).max(1 << 22);
+    let mut start = from;
+    while start < to {
+        let end = to.min(start.saturating_add(chunk_size));
+        let tx = tx.clone();
+        pool.execute(move || {
+            let mut stats = PersistenceStats::new(radix);
+            for (candidate, persistence) in PersistenceRange::new(start, end, radix) {
+                stats.add(candidate, persistence);
+            }
+            tx.send(stats).expect("Failed to send PersistenceStats");
+        });
+        start = end;
+    }
+    drop(tx);
+
+    let mut stats = PersistenceStats::new(radix);
+    for chunk_stats in rx {
+        stats.merge(&chunk_stats);
+    }
+    stats
+}
+
+/// Evaluate an integer argument, which may be an arithmetic expression such as 2^100*3^50.
+fn parse_integer(value: &str, name: &str) -> BigUint {
+    evaluate(value).unwrap_or_else(|error| panic!("Invalid integer for {}: {}", name, error))
+}
+
+/// Evaluate an integer argument that must fit in 64 bits.
+fn parse_u64(value: &str, name: &str) -> u64 {
+    parse_integer(value, name)
+        .to_u64()
+        .unwrap_or_else(|| panic!("Integer for {} must be below 2^64", name))
+}
+
+/// Check a radix given on the command line, for clap to report.
+fn validate_radix(value: String) -> Result<(), String> {
+    parse_radix(&value).map(|_| ())
+}
+
+/// Check comma separated radices given on the command line, for clap to report.
+fn validate_radices(value: String) -> Result<(), String> {
+    value
+        .split(',')
+        .try_for_each(|radix| parse_radix(radix).map(|_| ()))
+}
+
+/// Parse a radix from the command line, exiting with an error if it is invalid.
+fn radix_value(value: &str) -> u32 {
+    parse_radix(value).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    })
+}
+
+/// Parse an inclusive range of radices such as 2..36.
+fn parse_radix_range(value: &str) -> RangeInclusive<u32> {
+    let bounds: Vec<u32> = value
+        .split("..")
+        .map(|bound| bound.parse().expect("Invalid integer for radix range"))
+        .collect();
+    match bounds[..] {
+        [from, to] if 2 <= from && from <= to && to <= 36 => from..=to,
+        _ => panic!("Invalid radix range {}.", value),
+    }
+}
+
+/// Reported for integers whose digit products never reach a single digit.
+const LOOP_MESSAGE: &str = "Digit products loop without reaching a single digit";
+
+/// Return the numeral system named on the command line, with the radix as its base.
+fn numeral_system(name: &str, radix: u32) -> Box<dyn NumeralSystem> {
+    match name {
+        "bijective" => Box::new(Bijective(radix)),
+        "negabase" => Box::new(Negabase(radix)),
+        "factorial" => Box::new(Factorial),
+        _ => Box::new(Standard(radix)),
     }
 }
 
@@ -68,19 +399,81 @@ pub fn main() {
         .about("Find multiplicative persistence values")
         .arg(
             Arg::with_name("radix")
-                .help("Radix (base) to use for string/int conversion")
+                .help("Radix (base) to use for string/int conversion, at least 2")
                 .takes_value(true)
+                .validator(validate_radix)
                 .short("r")
                 .long("radix"),
         )
+        .arg(
+            Arg::with_name("database")
+                .help("Record results of search and list in this SQLite file, creating it if needed")
+                .takes_value(true)
+                .long("database"),
+        )
         .subcommand(
             SubCommand::with_name("for")
-                .about("Get multiplicative persistence for a positive integer")
+                .about("Get multiplicative persistence for positive integers")
                 .arg(
                     Arg::with_name("candidate")
-                        .help("The integer to process")
+                        .help("The integers to process, or - to read them from stdin")
+                        .multiple(true)
+                        .index(1)
+                        .required_unless("input"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .help("Also read integers from this file, one per line")
+                        .takes_value(true)
+                        .short("i")
+                        .long("input"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Output format for each integer and its persistence")
+                        .takes_value(true)
+                        .possible_values(&["text", "csv", "jsonl"])
+                        .short("o")
+                        .long("format"),
+                )
+                .arg(
+                    Arg::with_name("threads")
+                        .help("Number of threads to use. Default or 0 uses all cores.")
+                        .takes_value(true)
+                        .short("t")
+                        .long("threads"),
+                )
+                .arg(
+                    Arg::with_name("native")
+                        .help("Read integers in the notation of the radix, e.g. 1:0:59 in radix 60")
+                        .long("native"),
+                )
+                .arg(
+                    Arg::with_name("system")
+                        .help("Numeral system to write integers in, using the radix as its base")
+                        .takes_value(true)
+                        .possible_values(&["standard", "bijective", "negabase", "factorial"])
+                        .conflicts_with("native")
+                        .short("s")
+                        .long("system"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Show each step of the digit product chain for a positive integer")
+                .arg(
+                    Arg::with_name("candidate")
+                        .help("The integer to explain")
                         .index(1)
                         .required(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Output format")
+                        .takes_value(true)
+                        .possible_values(&["text", "markdown"])
+                        .short("o")
+                        .long("format"),
                 ),
         )
         .subcommand(
@@ -97,6 +490,368 @@ pub fn main() {
                         .help("Start of the range (exclusive)")
                         .index(2)
                         .required(true),
+                )
+                .arg(
+                    Arg::with_name("output_binary")
+                        .help("Write a binary persistence table to this file instead of text")
+                        .takes_value(true)
+                        .short("o")
+                        .long("output-binary"),
+                )
+                .arg(
+                    Arg::with_name("packed")
+                        .help("Pack two values into each byte of the binary table")
+                        .requires("output_binary")
+                        .short("p")
+                        .long("packed"),
+                )
+                .arg(
+                    Arg::with_name("system")
+                        .help("Numeral system to write integers in, using the radix as its base")
+                        .takes_value(true)
+                        .possible_values(&["standard", "bijective", "negabase", "factorial"])
+                        .conflicts_with("output_binary")
+                        .short("s")
+                        .long("system"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Summarise multiplicative persistence values over a range or list")
+                .arg(
+                    Arg::with_name("from_int")
+                        .help("Start of the range (inclusive)")
+                        .index(1)
+                        .required_unless_one(&["input", "binary"]),
+                )
+                .arg(
+                    Arg::with_name("to_int")
+                        .help("End of the range (exclusive)")
+                        .index(2)
+                        .required_unless_one(&["input", "binary"]),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .help("Read the text output of list from this file instead")
+                        .takes_value(true)
+                        .conflicts_with_all(&["from_int", "binary"])
+                        .short("i")
+                        .long("input"),
+                )
+                .arg(
+                    Arg::with_name("binary")
+                        .help("Read a binary persistence table from this file instead")
+                        .takes_value(true)
+                        .conflicts_with("from_int")
+                        .short("b")
+                        .long("binary"),
+                )
+                .arg(
+                    Arg::with_name("threads")
+                        .help("Number of threads to use for a range. Default or 0 uses all cores.")
+                        .takes_value(true)
+                        .short("t")
+                        .long("threads"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Check the output of list, search or for, recomputing each persistence")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The results to check, as text, CSV or JSON lines, or - for stdin")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("records")
+                        .help("Read search records, checking that they increase and are minimal")
+                        .long("records"),
+                )
+                .arg(
+                    Arg::with_name("minimality_limit")
+                        .help("Check minimality of records up to this integer. Default 10^8.")
+                        .takes_value(true)
+                        .requires("records")
+                        .short("l")
+                        .long("minimality-limit"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("count")
+                .about(
+                    "Count integers below radix^digits with each multiplicative persistence value",
+                )
+                .arg(
+                    Arg::with_name("digits")
+                        .help("Maximum length of integers to count, in digits")
+                        .index(1)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("smooth")
+                .about("Search backwards from digit products for high multiplicative persistence")
+                .arg(
+                    Arg::with_name("digits")
+                        .help("Maximum length of digit products to search, in digits")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .help("Report every zero-free digit product, not just new records")
+                        .short("a")
+                        .long("all"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("inverse")
+                .about("Get the smallest integer whose digits multiply to a value")
+                .arg(
+                    Arg::with_name("value")
+                        .help("The digit product to invert")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("native")
+                        .help("Write the integer in the notation of the radix, e.g. 1:0:59 in radix 60")
+                        .long("native"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tree")
+                .about("Export the tree of integers feeding into each single digit")
+                .arg(
+                    Arg::with_name("length")
+                        .help("Maximum length of integers in the tree, in digits")
+                        .takes_value(true)
+                        .short("l")
+                        .long("length"),
+                )
+                .arg(
+                    Arg::with_name("depth")
+                        .help("Maximum depth (multiplicative persistence) to expand to")
+                        .takes_value(true)
+                        .short("d")
+                        .long("depth"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("Output format")
+                        .takes_value(true)
+                        .possible_values(&["dot", "json"])
+                        .short("o")
+                        .long("format"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("permutations")
+                .about("List the distinct arrangements of an integer's digits")
+                .arg(
+                    Arg::with_name("candidate")
+                        .help("The integer to rearrange")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("ones")
+                        .help("Also insert up to this many extra 1 digits")
+                        .takes_value(true)
+                        .short("k")
+                        .long("ones"),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .help("Only print the number of arrangements")
+                        .short("c")
+                        .long("count"),
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .help("Only include arrangements matching this filter")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .possible_values(&["prime", "palindrome"])
+                        .short("f")
+                        .long("filter"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("primes")
+                .about("Search for the lowest prime with each multiplicative persistence value")
+                .arg(
+                    Arg::with_name("max_length")
+                        .help("Maximum length of primes to search, in digits")
+                        .takes_value(true)
+                        .short("l")
+                        .long("max-length"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sequence")
+                .about("Get multiplicative persistence along a named integer sequence")
+                .arg(
+                    Arg::with_name("sequence")
+                        .help("The sequence to generate")
+                        .possible_values(&[
+                            "powers",
+                            "factorials",
+                            "fibonacci",
+                            "repunits",
+                            "primorials",
+                            "recurrence",
+                        ])
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .help("Number of terms to generate, from index 0")
+                        .takes_value(true)
+                        .short("n")
+                        .long("count"),
+                )
+                .arg(
+                    Arg::with_name("base")
+                        .help("Base of powers (default 2) or repunits (default 10)")
+                        .takes_value(true)
+                        .short("b")
+                        .long("base"),
+                )
+                .arg(
+                    Arg::with_name("coefficients")
+                        .help("Comma separated coefficients c1,...,cd of a(k) = c1*a(k-1) + ... + cd*a(k-d)")
+                        .takes_value(true)
+                        .required_if("sequence", "recurrence")
+                        .short("c")
+                        .long("coefficients"),
+                )
+                .arg(
+                    Arg::with_name("initial")
+                        .help("Comma separated initial terms of the recurrence, at least one per coefficient")
+                        .takes_value(true)
+                        .required_if("sequence", "recurrence")
+                        .short("i")
+                        .long("initial"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sample")
+                .about("Estimate the distribution of multiplicative persistence for long integers")
+                .arg(
+                    Arg::with_name("lengths")
+                        .help("Comma separated lengths of integers to sample, in digits")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("samples")
+                        .help("Number of samples for each length")
+                        .takes_value(true)
+                        .short("n")
+                        .long("samples"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .help("Seed for the random generator, to reproduce a previous run")
+                        .takes_value(true)
+                        .short("s")
+                        .long("seed"),
+                )
+                .arg(
+                    Arg::with_name("multisets")
+                        .help("Sample uniformly random digit multisets, rather than integers")
+                        .short("m")
+                        .long("multisets"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("multibase")
+                .about("Search a range below 2^64 for integers with high multiplicative persistence in several radices")
+                .arg(
+                    Arg::with_name("from_int")
+                        .help("Start of the range (inclusive)")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("to_int")
+                        .help("End of the range (exclusive), at most 2^64 - 1")
+                        .index(2)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("radices")
+                        .help("Comma separated radices to score each integer in, e.g. 10,12")
+                        .takes_value(true)
+                        .validator(validate_radices)
+                        .required(true)
+                        .short("b")
+                        .long("radices"),
+                )
+                .arg(
+                    Arg::with_name("pareto")
+                        .help("Report integers not beaten in every radix, rather than by lowest persistence")
+                        .short("p")
+                        .long("pareto"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("oeis")
+                .about("Write a persistence sequence as an OEIS b-file")
+                .arg(
+                    Arg::with_name("sequence")
+                        .help(
+                            "persistence: persistence of n (A031346), \
+                             smallest: smallest integer with persistence n (A003001), \
+                             counts: integers below radix^digits with persistence n",
+                        )
+                        .takes_value(true)
+                        .possible_values(&["persistence", "smallest", "counts"])
+                        .required(true)
+                        .short("s")
+                        .long("sequence"),
+                )
+                .arg(
+                    Arg::with_name("terms")
+                        .help("Number of terms of persistence. Default 10001.")
+                        .takes_value(true)
+                        .short("n")
+                        .long("terms"),
+                )
+                .arg(
+                    Arg::with_name("rounds")
+                        .help("Search rounds for smallest. Default 15.")
+                        .takes_value(true)
+                        .long("rounds"),
+                )
+                .arg(
+                    Arg::with_name("digits")
+                        .help("Length in digits of the integers counted by counts")
+                        .takes_value(true)
+                        .required_if("sequence", "counts")
+                        .short("d")
+                        .long("digits"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .help("Write the b-file to this file instead of stdout")
+                        .takes_value(true)
+                        .short("o")
+                        .long("output"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("records")
+                .about("Print the known smallest integer with each multiplicative persistence value")
+                .arg(
+                    Arg::with_name("radix_range")
+                        .help("Print every radix in this inclusive range, e.g. 2..36")
+                        .takes_value(true)
+                        .long("radix-range"),
                 ),
         )
         .subcommand(
@@ -116,6 +871,104 @@ pub fn main() {
                         .short("n")
                         .long("rounds"),
                 )
+                .arg(
+                    Arg::with_name("threads")
+                        .help("Number of threads to use. Default or 0 uses all cores.")
+                        .takes_value(true)
+                        .short("t")
+                        .long("threads"),
+                )
+                .arg(
+                    Arg::with_name("digits")
+                        .help("Only use these digits, e.g. 238")
+                        .takes_value(true)
+                        .long("digits"),
+                )
+                .arg(
+                    Arg::with_name("exclude_digits")
+                        .help("Never use these digits, e.g. 7")
+                        .takes_value(true)
+                        .long("exclude-digits"),
+                )
+                .arg(
+                    Arg::with_name("require_digit")
+                        .help("Only report integers containing this digit")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .long("require-digit"),
+                )
+                .arg(
+                    Arg::with_name("radix_range")
+                        .help("Search every radix in this inclusive range, e.g. 2..36")
+                        .takes_value(true)
+                        .conflicts_with_all(&["digits", "exclude_digits", "require_digit"])
+                        .long("radix-range"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("coordinator")
+                .about("Search like search, handing out work to workers connecting over TCP")
+                .arg(
+                    Arg::with_name("bind")
+                        .help("Address to listen on, e.g. 127.0.0.1:7878")
+                        .takes_value(true)
+                        .required(true)
+                        .long("bind"),
+                )
+                .arg(
+                    Arg::with_name("from_round")
+                        .help("Search round to start from (length of integer in digits)")
+                        .takes_value(true)
+                        .short("f")
+                        .long("from-round"),
+                )
+                .arg(
+                    Arg::with_name("rounds")
+                        .help("Number of search rounds to perform")
+                        .takes_value(true)
+                        .short("n")
+                        .long("rounds"),
+                )
+                .arg(
+                    Arg::with_name("unit_size")
+                        .help("Most candidates in each work unit. Default 100000.")
+                        .takes_value(true)
+                        .long("unit-size"),
+                )
+                .arg(
+                    Arg::with_name("unit_timeout")
+                        .help("Seconds to wait for a worker to report on its unit before handing the unit to another worker. Default 600, or 0 to wait forever.")
+                        .takes_value(true)
+                        .long("unit-timeout"),
+                )
+                .arg(
+                    Arg::with_name("radix_range")
+                        .help("Search every radix in this inclusive range, e.g. 2..36")
+                        .takes_value(true)
+                        .long("radix-range"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Answer persistence queries and run searches over an HTTP/JSON API")
+                .arg(
+                    Arg::with_name("bind")
+                        .help("Address to listen on. Default 127.0.0.1:8080.")
+                        .takes_value(true)
+                        .long("bind"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("worker")
+                .about("Search work units handed out by a coordinator until it has no more")
+                .arg(
+                    Arg::with_name("connect")
+                        .help("Address of the coordinator, e.g. 127.0.0.1:7878")
+                        .takes_value(true)
+                        .required(true)
+                        .long("connect"),
+                )
                 .arg(
                     Arg::with_name("threads")
                         .help("Number of threads to use. Default or 0 uses all cores.")
@@ -127,36 +980,600 @@ pub fn main() {
     let matches = app.clone().get_matches();
 
     if let (subcommand_name, Some(subcommand_matches)) = matches.subcommand() {
-        let radix = matches
-            .value_of("radix")
-            .unwrap_or("10")
-            .parse()
-            .expect("Invalid integer for radix");
+        let radix = radix_value(matches.value_of("radix").unwrap_or("10"));
         trace!("cli radix: {}", radix);
         match subcommand_name {
             "for" => {
-                let candidate: &str = subcommand_matches.value_of("candidate").unwrap();
-                println!("{}", multiplicative_persistence(candidate, radix));
+                let mut threads: usize = subcommand_matches
+                    .value_of("threads")
+                    .unwrap_or("0")
+                    .parse()
+                    .expect("Invalid integer for threads");
+                if threads == 0 {
+                    threads = num_cpus::get();
+                }
+                let options = ForOptions {
+                    radix,
+                    native: subcommand_matches.is_present("native"),
+                    system: subcommand_matches.value_of("system").map(String::from),
+                    format: subcommand_matches
+                        .value_of("format")
+                        .unwrap_or("text")
+                        .to_owned(),
+                };
+
+                let arguments: Vec<String> = subcommand_matches
+                    .values_of("candidate")
+                    .map(|values| values.map(String::from).collect())
+                    .unwrap_or_default();
+                let input_path = subcommand_matches.value_of("input");
+                // A single integer argument gives just its persistence, as it always has
+                if arguments.len() == 1
+                    && arguments[0] != "-"
+                    && input_path.is_none()
+                    && options.format == "text"
+                {
+                    match options.evaluate(&arguments[0]) {
+                        Ok((_, persistence)) => println!("{}", persistence),
+                        Err(error) => {
+                            eprintln!("{}", error);
+                            process::exit(1);
+                        }
+                    }
+                } else {
+                    let mut sources: Vec<(String, Box<dyn BufRead>)> = Vec::new();
+                    let mut lines: Vec<(String, String)> = Vec::new();
+                    for (i, argument) in arguments.into_iter().enumerate() {
+                        if argument == "-" {
+                            sources
+                                .push(("stdin".to_owned(), Box::new(BufReader::new(io::stdin()))));
+                        } else {
+                            lines.push((format!("argument {}", i + 1), argument));
+                        }
+                    }
+                    if let Some(input_path) = input_path {
+                        let file = File::open(input_path).expect("Couldn't open input file");
+                        sources.push((input_path.to_owned(), Box::new(BufReader::new(file))));
+                    }
+                    let candidates =
+                        lines
+                            .into_iter()
+                            .chain(sources.into_iter().flat_map(|(name, source)| {
+                                source
+                                    .lines()
+                                    .enumerate()
+                                    .map(move |(i, line)| {
+                                        let line = line.expect("Couldn't read input");
+                                        (format!("{} line {}", name, i + 1), line)
+                                    })
+                                    .filter(|(_, line)| !line.trim().is_empty())
+                            }));
+                    if !evaluate_batch(candidates, &options, threads) {
+                        process::exit(1);
+                    }
+                }
+            }
+            "explain" => {
+                let candidate: BigUint = parse_integer(
+                    subcommand_matches.value_of("candidate").unwrap(),
+                    "candidate",
+                );
+                let explanation = explain(&candidate, radix);
+                match subcommand_matches.value_of("format").unwrap_or("text") {
+                    "markdown" => print!("{}", explanation.to_markdown()),
+                    _ => print!("{}", explanation.to_text()),
+                }
             }
             "list" => {
-                let from_int: usize = subcommand_matches
-                    .value_of("from_int")
+                let from_int: u64 =
+                    parse_u64(subcommand_matches.value_of("from_int").unwrap(), "from");
+                let to_int: u64 = parse_u64(subcommand_matches.value_of("to_int").unwrap(), "to");
+                let results: Box<dyn Iterator<Item = (u64, usize)>> = match subcommand_matches
+                    .value_of("system")
+                {
+                    Some(system) if system != "standard" => {
+                        let system = numeral_system(system, radix);
+                        Box::new((from_int..to_int).filter_map(move |n| {
+                            match multiplicative_persistence_in(&BigUint::from(n), system.as_ref())
+                            {
+                                Some(persistence) => Some((n, persistence)),
+                                None => {
+                                    eprintln!("{}: {}", n, LOOP_MESSAGE);
+                                    None
+                                }
+                            }
+                        }))
+                    }
+                    // Standard radices have a much faster range implementation
+                    _ => Box::new(PersistenceRange::new(from_int, to_int, radix)),
+                };
+
+                // Other numeral systems give other results, so are recorded separately
+                let operation = match subcommand_matches.value_of("system") {
+                    Some(system) if system != "standard" => format!("list-{}", system),
+                    _ => "list".to_owned(),
+                };
+                let mut database = open_database(&matches, &operation, radix);
+                let recording = database.is_some();
+                let mut pending: Vec<(BigUint, usize)> = Vec::new();
+                let mut record = |pending: &mut Vec<(BigUint, usize)>| {
+                    if let Some((database, run_id)) = database.as_mut() {
+                        database
+                            .add_results(
+                                *run_id,
+                                &operation,
+                                radix,
+                                pending.iter().map(|(candidate, p)| (candidate, *p)),
+                            )
+                            .expect("Failed to write database");
+                    }
+                    pending.clear();
+                };
+                let results = results.inspect(|(candidate, persistence)| {
+                    if recording {
+                        pending.push((BigUint::from(*candidate), *persistence));
+                        if pending.len() >= 1 << 16 {
+                            record(&mut pending);
+                        }
+                    }
+                });
+
+                match subcommand_matches.value_of("output_binary") {
+                    Some(output_path) => {
+                        let header = Header {
+                            radix,
+                            start: from_int,
+                            count: to_int.saturating_sub(from_int),
+                            packed: subcommand_matches.is_present("packed"),
+                        };
+                        let file = File::create(output_path).expect("Couldn't create file");
+                        let mut writer = TableWriter::new(BufWriter::new(file), header)
+                            .expect("Failed to write table header");
+                        for (_, persistence) in results {
+                            writer.push(persistence).expect("Failed to write table");
+                        }
+                        writer.finish().expect("Failed to write table");
+                    }
+                    None => {
+                        let stdout = io::stdout();
+                        let mut out = BufWriter::new(stdout.lock());
+                        for (candidate, persistence) in results {
+                            writeln!(out, "{} {}", candidate, persistence)
+                                .expect("Failed to write output");
+                        }
+                    }
+                }
+                record(&mut pending);
+                if let Some((database, run_id)) = database {
+                    database
+                        .finish_run(run_id)
+                        .expect("Failed to write database");
+                }
+            }
+            "stats" => {
+                let stats = if let Some(input_path) = subcommand_matches.value_of("input") {
+                    let file = File::open(input_path).expect("Couldn't open input file");
+                    let mut stats = PersistenceStats::new(radix);
+                    for (i, line) in BufReader::new(file).lines().enumerate() {
+                        let line = line.expect("Couldn't read input file");
+                        let mut fields = line.split(' ').map(|field| field.parse().ok());
+                        match (fields.next(), fields.next(), fields.next()) {
+                            (Some(Some(candidate)), Some(Some(persistence)), None) => {
+                                stats.add(candidate, persistence as usize)
+                            }
+                            _ => panic!("Invalid list output on line {}: {}", i + 1, line),
+                        }
+                    }
+                    stats
+                } else if let Some(binary_path) = subcommand_matches.value_of("binary") {
+                    let table = Table::open(binary_path).expect("Couldn't open table");
+                    let mut stats = PersistenceStats::new(table.header().radix);
+                    for (candidate, persistence) in table.iter() {
+                        stats.add(candidate, persistence);
+                    }
+                    stats
+                } else {
+                    let from_int: u64 =
+                        parse_u64(subcommand_matches.value_of("from_int").unwrap(), "from");
+                    let to_int: u64 =
+                        parse_u64(subcommand_matches.value_of("to_int").unwrap(), "to");
+                    let mut threads: usize = subcommand_matches
+                        .value_of("threads")
+                        .unwrap_or("0")
+                        .parse()
+                        .expect("Invalid integer for threads");
+                    if threads == 0 {
+                        threads = num_cpus::get();
+                    }
+                    range_stats(from_int, to_int, radix, threads)
+                };
+
+                println!("total {}", stats.total());
+                if let Some(mean) = stats.mean() {
+                    println!("mean {:.6}", mean);
+                }
+                for (persistence, count) in stats.counts.iter().enumerate() {
+                    if let (Some(first), Some(last)) =
+                        (stats.first[persistence], stats.last[persistence])
+                    {
+                        println!("persistence {} {} {} {}", persistence, count, first, last);
+                    }
+                }
+                for (decade, counts) in stats.decades.iter().enumerate() {
+                    for (persistence, count) in counts.iter().enumerate() {
+                        if *count > 0 {
+                            println!(
+                                "decade {} {} {}",
+                                stats.decade_start(decade),
+                                persistence,
+                                count
+                            );
+                        }
+                    }
+                }
+            }
+            "verify" => {
+                let records = subcommand_matches.is_present("records");
+                let minimality_limit = subcommand_matches
+                    .value_of("minimality_limit")
+                    .map_or(100_000_000, |limit| parse_u64(limit, "minimality limit"));
+                let input: Box<dyn BufRead> = match subcommand_matches.value_of("file").unwrap() {
+                    "-" => Box::new(BufReader::new(io::stdin())),
+                    path => Box::new(BufReader::new(
+                        File::open(path).expect("Couldn't open input file"),
+                    )),
+                };
+
+                let mut parser = ResultsParser::new(records, radix);
+                let mut claims = Vec::new();
+                let mut failures = Vec::new();
+                for (i, line) in input.lines().enumerate() {
+                    let line = line.expect("Couldn't read input");
+                    match parser.parse(i + 1, &line) {
+                        Ok(Some(claim)) => claims.push(claim),
+                        Ok(None) => {}
+                        Err(message) => failures.push(Failure {
+                            line: i + 1,
+                            message,
+                        }),
+                    }
+                }
+                failures.extend(verify_claims(&claims, records, minimality_limit));
+                failures.sort_by_key(|failure| failure.line);
+
+                for failure in failures.iter() {
+                    println!("line {}: {}", failure.line, failure.message);
+                }
+                println!(
+                    "checked {} claims, {} failures",
+                    claims.len(),
+                    failures.len()
+                );
+                if failures.is_empty() {
+                    println!("PASS");
+                } else {
+                    println!("FAIL");
+                    process::exit(1);
+                }
+            }
+            "count" => {
+                let digits: usize = subcommand_matches
+                    .value_of("digits")
                     .unwrap()
                     .parse()
-                    .expect("Invalid integer for from");
-                let to_int: usize = subcommand_matches
-                    .value_of("to_int")
+                    .expect("Invalid integer for digits");
+                for (persistence, count) in persistence_counts(digits, radix).iter().enumerate() {
+                    println!("{} {}", persistence, count);
+                }
+            }
+            "smooth" => {
+                let digits: usize = subcommand_matches
+                    .value_of("digits")
                     .unwrap()
                     .parse()
-                    .expect("Invalid integer for to");
-                for candidate in from_int..to_int {
-                    println!(
-                        "{} {}",
-                        candidate,
-                        multiplicative_persistence(&candidate.to_string(), radix)
+                    .expect("Invalid integer for digits");
+                let bound: BigUint = Pow::pow(&BigUint::from(radix), digits) - BigUint::one();
+                let all = subcommand_matches.is_present("all");
+                let mut current_max = 0;
+                for result in smooth_search(&bound, radix) {
+                    if all || result.multiplicative_persistence > current_max {
+                        current_max = current_max.max(result.multiplicative_persistence);
+                        println!("{} {}", result.multiplicative_persistence, result.product);
+                    }
+                }
+            }
+            "inverse" => {
+                let value: BigUint =
+                    parse_integer(subcommand_matches.value_of("value").unwrap(), "value");
+                match smallest_preimage(&value, radix) {
+                    Some(preimage) => {
+                        if subcommand_matches.is_present("native") {
+                            println!("{}", to_notation(&preimage, radix));
+                        } else {
+                            println!("{}", preimage);
+                        }
+                    }
+                    None => {
+                        eprintln!("No integer has digit product {}", value);
+                        process::exit(1);
+                    }
+                }
+            }
+            "tree" => {
+                let length: usize = subcommand_matches
+                    .value_of("length")
+                    .unwrap_or("4")
+                    .parse()
+                    .expect("Invalid integer for length");
+                let depth: usize = subcommand_matches
+                    .value_of("depth")
+                    .unwrap_or("3")
+                    .parse()
+                    .expect("Invalid integer for depth");
+                let roots = preimage_tree(radix, length, depth);
+                match subcommand_matches.value_of("format").unwrap_or("dot") {
+                    "json" => print!("{}", to_json(&roots)),
+                    _ => print!("{}", to_dot(&roots)),
+                }
+            }
+            "permutations" => {
+                let candidate: BigUint = parse_integer(
+                    subcommand_matches.value_of("candidate").unwrap(),
+                    "candidate",
+                );
+                let ones: usize = subcommand_matches
+                    .value_of("ones")
+                    .unwrap_or("0")
+                    .parse()
+                    .expect("Invalid integer for ones");
+                let filters: Vec<&str> = subcommand_matches
+                    .values_of("filter")
+                    .map(|values| values.collect())
+                    .unwrap_or_default();
+                let digits = to_digits(&candidate, radix);
+
+                let results = arrangements(&digits, ones)
+                    .filter(|arrangement| {
+                        !filters.contains(&"palindrome")
+                            || arrangement.iter().eq(arrangement.iter().rev())
+                    })
+                    .map(|arrangement| from_digits(&arrangement, radix))
+                    .filter(|value| !filters.contains(&"prime") || is_prime(value));
+                if !subcommand_matches.is_present("count") {
+                    let stdout = io::stdout();
+                    let mut out = BufWriter::new(stdout.lock());
+                    for value in results {
+                        writeln!(out, "{}", value).expect("Failed to write output");
+                    }
+                } else if filters.is_empty() {
+                    // Without filters, we can count arrangements without enumerating them
+                    println!("{}", count_arrangements(&digits, ones));
+                } else {
+                    println!("{}", results.count());
+                }
+            }
+            "primes" => {
+                let max_length: usize = subcommand_matches
+                    .value_of("max_length")
+                    .unwrap_or("12")
+                    .parse()
+                    .expect("Invalid integer for max_length");
+                for SearchResult {
+                    candidate,
+                    multiplicative_persistence,
+                } in SmallestPrimes::new(max_length, radix)
+                {
+                    println!("{} {}", multiplicative_persistence, candidate);
+                }
+            }
+            "sequence" => {
+                let count: usize = subcommand_matches
+                    .value_of("count")
+                    .unwrap_or("1000")
+                    .parse()
+                    .expect("Invalid integer for count");
+                let base = |default| -> u32 {
+                    subcommand_matches
+                        .value_of("base")
+                        .unwrap_or(default)
+                        .parse()
+                        .expect("Invalid integer for base")
+                };
+                let parse_terms = |option| -> Vec<BigUint> {
+                    subcommand_matches
+                        .value_of(option)
+                        .unwrap()
+                        .split(',')
+                        .map(|term| parse_integer(term, option))
+                        .collect()
+                };
+                let sequence = match subcommand_matches.value_of("sequence").unwrap() {
+                    "powers" => Sequence::Powers(base("2")),
+                    "factorials" => Sequence::Factorials,
+                    "fibonacci" => Sequence::Fibonacci,
+                    "repunits" => Sequence::Repunits(base("10")),
+                    "primorials" => Sequence::Primorials,
+                    _ => Sequence::recurrence(parse_terms("coefficients"), parse_terms("initial"))
+                        .unwrap_or_else(|error| {
+                            eprintln!("{}", error);
+                            process::exit(1);
+                        }),
+                };
+
+                let stdout = io::stdout();
+                let mut out = BufWriter::new(stdout.lock());
+                let mut max: Option<SequenceResult> = None;
+                for result in sequence_persistence(&sequence, radix).take(count) {
+                    writeln!(
+                        out,
+                        "{} {} {}",
+                        result.index, result.length, result.multiplicative_persistence
+                    )
+                    .expect("Failed to write output");
+                    if max.as_ref().is_none_or(|max| {
+                        result.multiplicative_persistence > max.multiplicative_persistence
+                    }) {
+                        max = Some(result);
+                    }
+                }
+                out.flush().expect("Failed to write output");
+                if let Some(max) = max {
+                    eprintln!(
+                        "max {} at index {}",
+                        max.multiplicative_persistence, max.index
                     );
                 }
             }
+            "sample" => {
+                let lengths: Vec<usize> = subcommand_matches
+                    .value_of("lengths")
+                    .unwrap()
+                    .split(',')
+                    .map(|length| length.parse().expect("Invalid integer for length"))
+                    .collect();
+                let samples: usize = subcommand_matches
+                    .value_of("samples")
+                    .unwrap_or("1000")
+                    .parse()
+                    .expect("Invalid integer for samples");
+                let seed: u64 = subcommand_matches
+                    .value_of("seed")
+                    .unwrap_or("0")
+                    .parse()
+                    .expect("Invalid integer for seed");
+                let mode = if subcommand_matches.is_present("multisets") {
+                    SampleMode::Multisets
+                } else {
+                    SampleMode::Integers
+                };
+
+                let mut rng = Rng::new(seed);
+                for length in lengths {
+                    let histogram = sample_persistence(length, samples, radix, mode, &mut rng);
+                    for (persistence, count) in histogram.counts.iter().enumerate() {
+                        if *count == 0 {
+                            continue;
+                        }
+                        // 95% confidence interval
+                        let (low, high) = histogram.confidence_interval(persistence, 1.96);
+                        println!(
+                            "{} {} {} {:.4} {:.4} {:.4}",
+                            length,
+                            persistence,
+                            count,
+                            histogram.proportion(persistence),
+                            low,
+                            high
+                        );
+                    }
+                }
+            }
+            "multibase" => {
+                let from_int: u64 =
+                    parse_u64(subcommand_matches.value_of("from_int").unwrap(), "from");
+                let to_int: u64 = parse_u64(subcommand_matches.value_of("to_int").unwrap(), "to");
+                let radices: Vec<u32> = subcommand_matches
+                    .value_of("radices")
+                    .unwrap()
+                    .split(',')
+                    .map(radix_value)
+                    .collect();
+                let record = if subcommand_matches.is_present("pareto") {
+                    MultibaseRecord::Pareto
+                } else {
+                    MultibaseRecord::Minimum
+                };
+                for MultibaseResult {
+                    candidate,
+                    multiplicative_persistence,
+                } in MultibaseSearch::new(from_int, to_int, &radices, record)
+                {
+                    let scores: Vec<String> = radices
+                        .iter()
+                        .zip(multiplicative_persistence.iter())
+                        .map(|(radix, persistence)| format!("{}:{}", radix, persistence))
+                        .collect();
+                    println!("{} {}", candidate, scores.join(" "));
+                }
+            }
+            "oeis" => {
+                let sequence = match subcommand_matches.value_of("sequence").unwrap() {
+                    "persistence" => OeisSequence::Persistence {
+                        terms: parse_u64(
+                            subcommand_matches.value_of("terms").unwrap_or("10001"),
+                            "terms",
+                        ),
+                    },
+                    "smallest" => OeisSequence::Smallest {
+                        rounds: subcommand_matches
+                            .value_of("rounds")
+                            .unwrap_or("15")
+                            .parse()
+                            .expect("Invalid integer for rounds"),
+                    },
+                    _ => OeisSequence::Counts {
+                        digits: subcommand_matches
+                            .value_of("digits")
+                            .unwrap()
+                            .parse()
+                            .expect("Invalid integer for digits"),
+                    },
+                };
+                let terms = match sequence.terms(radix) {
+                    Ok(terms) => terms,
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        process::exit(1);
+                    }
+                };
+                match subcommand_matches.value_of("output") {
+                    Some(output_path) => {
+                        let file = File::create(output_path).expect("Couldn't create file");
+                        write_b_file(&mut BufWriter::new(file), sequence.offset(), terms)
+                    }
+                    None => {
+                        let stdout = io::stdout();
+                        let mut out = BufWriter::new(stdout.lock());
+                        write_b_file(&mut out, sequence.offset(), terms)
+                    }
+                }
+                .expect("Failed to write b-file");
+            }
+            "records" => {
+                let radices = match subcommand_matches.value_of("radix_range") {
+                    Some(radix_range) => parse_radix_range(radix_range),
+                    None => radix..=radix,
+                };
+                for radix in radices.clone() {
+                    let records = known_records(radix)
+                        .unwrap_or_else(|| panic!("No known records for radix {}.", radix));
+                    eprintln!(
+                        "radix {} searched up to {} digits",
+                        radix, records.searched_digits
+                    );
+                    for persistence in 0..=records.max_persistence() {
+                        let smallest = records.smallest(persistence).unwrap();
+                        if radices.start() != radices.end() {
+                            println!(
+                                "{} {} {} {}",
+                                radix,
+                                persistence,
+                                smallest,
+                                to_notation(&smallest, radix)
+                            );
+                        } else if radix != 10 {
+                            println!(
+                                "{} {} {}",
+                                persistence,
+                                smallest,
+                                to_notation(&smallest, radix)
+                            );
+                        } else {
+                            println!("{} {}", persistence, smallest);
+                        }
+                    }
+                }
+            }
             "search" => {
                 let from_round: usize = subcommand_matches
                     .value_of("from_round")
@@ -180,8 +1597,159 @@ pub fn main() {
                 } else if threads > num_cpus::get() {
                     panic!("Invalid number of threads given.")
                 }
+                // Candidates are built from alphanumeric digits
+                if radix > 36 {
+                    panic!("Search only supports radices up to 36.")
+                }
+
+                let radices = if let Some(radix_range) = subcommand_matches.value_of("radix_range")
+                {
+                    parse_radix_range(radix_range)
+                        .map(CandidateDigits::for_radix)
+                        .collect()
+                // Only prune digits generally if the digits are not restricted
+                } else if ["digits", "exclude_digits", "require_digit"]
+                    .iter()
+                    .any(|option| subcommand_matches.is_present(option))
+                {
+                    let parse_digits = |option| -> Vec<char> {
+                        subcommand_matches
+                            .values_of(option)
+                            .map(|values| {
+                                values
+                                    .flat_map(|value| value.chars())
+                                    .map(|digit| digit.to_ascii_lowercase())
+                                    .collect()
+                            })
+                            .unwrap_or_default()
+                    };
+                    let excluded = parse_digits("exclude_digits");
+                    let allowed: Vec<char> = match subcommand_matches.value_of("digits") {
+                        Some(_) => parse_digits("digits"),
+                        None => (0..radix)
+                            .map(|digit| std::char::from_digit(digit, radix).unwrap())
+                            .collect(),
+                    }
+                    .into_iter()
+                    .filter(|digit| !excluded.contains(digit))
+                    .collect();
+                    let required = parse_digits("require_digit");
+                    if let Some(digit) = required.iter().find(|digit| excluded.contains(digit)) {
+                        panic!("Digit {} is both required and excluded.", digit)
+                    }
+                    if let Some(digit) = allowed
+                        .iter()
+                        .chain(required.iter())
+                        .find(|digit| !digit.is_digit(radix))
+                    {
+                        panic!("Invalid digit {}.", digit)
+                    }
+                    vec![CandidateDigits::restricted(radix, &allowed, &required)]
+                } else {
+                    vec![CandidateDigits::for_radix(radix)]
+                };
+
+                let mut database = open_database(&matches, "search", radix);
+                search(from_round, num_rounds, threads, &radices, database.as_mut());
+                if let Some((database, run_id)) = database {
+                    database
+                        .finish_run(run_id)
+                        .expect("Failed to write database");
+                }
+            }
+            "coordinator" => {
+                let from_round: usize = subcommand_matches
+                    .value_of("from_round")
+                    .unwrap_or("0")
+                    .parse()
+                    .expect("Invalid integer for from_round");
+                let num_rounds: usize = subcommand_matches
+                    .value_of("rounds")
+                    .unwrap_or("15")
+                    .parse()
+                    .expect("Invalid integer for rounds");
+                let unit_size: u64 = subcommand_matches
+                    .value_of("unit_size")
+                    .unwrap_or("100000")
+                    .parse()
+                    .expect("Invalid integer for unit_size");
+                if unit_size == 0 {
+                    panic!("Invalid unit size given.")
+                }
+                let unit_timeout = match subcommand_matches
+                    .value_of("unit_timeout")
+                    .unwrap_or("600")
+                    .parse()
+                    .expect("Invalid integer for unit_timeout")
+                {
+                    0 => None,
+                    seconds => Some(Duration::from_secs(seconds)),
+                };
+                let radices: Vec<CandidateDigits> =
+                    match subcommand_matches.value_of("radix_range") {
+                        Some(radix_range) => parse_radix_range(radix_range),
+                        None => radix..=radix,
+                    }
+                    .map(CandidateDigits::for_radix)
+                    .collect();
 
-                search(from_round, num_rounds, threads)
+                let listener = TcpListener::bind(subcommand_matches.value_of("bind").unwrap())
+                    .expect("Couldn't listen on address");
+                let units = match work_units(&radices, from_round, num_rounds, unit_size) {
+                    Ok(units) => units,
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        process::exit(1);
+                    }
+                };
+                eprintln!(
+                    "coordinating {} work units on {}",
+                    units.len(),
+                    listener.local_addr().expect("Couldn't read address")
+                );
+                let mut printer = RecordPrinter::new(radices.len() > 1, from_round);
+                coordinate(listener, units, unit_timeout, |unit, results| {
+                    info!("info: unit {} (round {}) complete", unit.id, unit.round);
+                    for result in results.iter() {
+                        printer.print(&unit.digits, result);
+                    }
+                })
+                .expect("Coordinator failed");
+            }
+            "serve" => {
+                let server = Server::bind(
+                    subcommand_matches
+                        .value_of("bind")
+                        .unwrap_or("127.0.0.1:8080"),
+                )
+                .expect("Couldn't listen on address");
+                eprintln!(
+                    "serving on http://{}",
+                    server.local_addr().expect("Couldn't read address")
+                );
+                server.serve();
+            }
+            "worker" => {
+                let mut threads: usize = subcommand_matches
+                    .value_of("threads")
+                    .unwrap_or("0")
+                    .parse()
+                    .expect("Invalid integer for threads");
+                if threads == 0 {
+                    threads = num_cpus::get();
+                }
+                let address = subcommand_matches.value_of("connect").unwrap().to_owned();
+                let workers: Vec<thread::JoinHandle<usize>> = (0..threads)
+                    .map(|_| {
+                        let address = address.clone();
+                        thread::spawn(move || work(address).expect("Worker failed"))
+                    })
+                    .collect();
+                let completed: usize = workers
+                    .into_iter()
+                    .map(|worker| worker.join().expect("Worker thread panicked"))
+                    .sum();
+                eprintln!("completed {} work units", completed);
             }
             _ => eprintln!("Invalid subcommand."),
         }
@@ -1,3 +1,29 @@
+use std::convert::TryFrom;
+
+/// Return the number of multisets of `n` elements from `pool_size` distinct elements, or
+/// nothing if there are too many to count.
+fn multisets(pool_size: usize, n: usize) -> Option<u64> {
+    if pool_size == 0 {
+        return Some(if n == 0 { 1 } else { 0 });
+    }
+    // (n + pool_size - 1) choose n, where each partial product is itself a binomial
+    let k = (pool_size - 1).min(n) as u128;
+    let total = (n + pool_size - 1) as u128;
+    let count = (0..k).try_fold(1u128, |acc, i| Some(acc.checked_mul(total - i)? / (i + 1)))?;
+    u64::try_from(count).ok()
+}
+
+/// Return the number of `n`-length combinations of `pool_size` elements, with replacement,
+/// or nothing if there are too many to count.
+pub fn count_combinations(pool_size: usize, n: usize) -> Option<u64> {
+    // Matching the iterator, which yields nothing for a length of zero
+    if n == 0 {
+        Some(0)
+    } else {
+        multisets(pool_size, n)
+    }
+}
+
 /// An iterator for all the `n`-length combinations of a collection, with replacement.
 #[derive(Debug, Clone)]
 pub struct CombinationsWithReplacement<T: Copy> {
@@ -19,7 +45,7 @@ impl<T: Copy> CombinationsWithReplacement<T> {
     /// Create a new CombinationsWithReplacement iterator from a `Vec` of `Copy`-able elements.
     pub fn new(pool: Vec<T>, n: usize) -> CombinationsWithReplacement<T> {
         // If either the pool is empty or the size is zero, return None immediately
-        let empty = n == 0 || pool.len() == 0;
+        let empty = n == 0 || pool.is_empty();
         CombinationsWithReplacement {
             mask_max_value: if empty { 0 } else { pool.len() - 1 },
 
@@ -31,6 +57,35 @@ impl<T: Copy> CombinationsWithReplacement<T> {
         }
     }
 
+    /// Create an iterator starting from the combination at `rank`, counting from zero in
+    /// the order `new` yields them.
+    pub fn starting_at(pool: Vec<T>, n: usize, rank: u64) -> CombinationsWithReplacement<T> {
+        let mut combinations = CombinationsWithReplacement::new(pool, n);
+        if count_combinations(combinations.pool.len(), n).is_some_and(|count| rank >= count) {
+            combinations.empty = true;
+            return combinations;
+        }
+        // Choose each index in turn, skipping past every combination with a smaller one
+        let mut rank = rank;
+        let mut lowest = 0;
+        for position in 0..n {
+            let remaining = n - position - 1;
+            let mut index = lowest;
+            loop {
+                // Too many to count is certainly more than any rank
+                let with_index = match multisets(combinations.pool.len() - index, remaining) {
+                    Some(with_index) if rank >= with_index => with_index,
+                    _ => break,
+                };
+                rank -= with_index;
+                index += 1;
+            }
+            combinations.mask[position] = index;
+            lowest = index;
+        }
+        combinations
+    }
+
     /// Map the current mask over the pool to get an output combination
     fn current(&self) -> Vec<T> {
         self.mask.iter().map(|i| self.pool[*i]).collect()
@@ -113,4 +168,39 @@ mod tests {
         let combinations: Vec<Vec<char>> = CombinationsWithReplacement::new(vec![], 2).collect();
         assert_eq!(combinations, empty_char_combinations);
     }
+
+    #[test]
+    fn test_count_combinations() {
+        for pool_size in 0..6 {
+            for n in 0..6 {
+                let pool: Vec<usize> = (0..pool_size).collect();
+                assert_eq!(
+                    count_combinations(pool_size, n),
+                    Some(CombinationsWithReplacement::new(pool, n).count() as u64)
+                );
+            }
+        }
+        assert_eq!(count_combinations(4, 250), Some(2_667_126));
+        assert_eq!(count_combinations(34, 40), None);
+        assert_eq!(count_combinations(100, 5000), None);
+    }
+
+    #[test]
+    fn test_starting_at() {
+        let all: Vec<Vec<char>> =
+            CombinationsWithReplacement::new(vec!['A', 'B', 'C'], 4).collect();
+        for rank in 0..=all.len() {
+            let rest: Vec<Vec<char>> =
+                CombinationsWithReplacement::starting_at(vec!['A', 'B', 'C'], 4, rank as u64)
+                    .collect();
+            assert_eq!(rest, all[rank..].to_vec());
+        }
+
+        // Ranks still work in spaces too large to count
+        let pool: Vec<usize> = (0..34).collect();
+        let mut expected = vec![0; 40];
+        expected[39] = 5;
+        let mut huge = CombinationsWithReplacement::starting_at(pool, 40, 5);
+        assert_eq!(huge.next(), Some(expected));
+    }
 }
@@ -0,0 +1,453 @@
+use std::collections::{HashMap, HashSet};
+
+use num_bigint::BigUint;
+use num_traits::{One, Pow, Zero};
+
+use super::multiplicative_persistence_uint;
+
+/// Return the binomial coefficient `n` choose `k`.
+pub(crate) fn binomial(n: usize, k: usize) -> BigUint {
+    if k > n {
+        return BigUint::zero();
+    }
+    (0..k.min(n - k)).fold(BigUint::one(), |acc, i| {
+        acc * BigUint::from(n - i) / BigUint::from(i + 1)
+    })
+}
+
+/// Return the exponent of each prime in `n`, or nothing if `n` has other prime factors.
+fn prime_exponents(mut n: u32, primes: &[u32]) -> Option<Vec<u32>> {
+    let exponents = primes
+        .iter()
+        .map(|prime| {
+            let mut exponent = 0;
+            while n.is_multiple_of(*prime) {
+                n /= prime;
+                exponent += 1;
+            }
+            exponent
+        })
+        .collect();
+    match n {
+        1 => Some(exponents),
+        _ => None,
+    }
+}
+
+/// Add `count` integers to the histogram bucket for `persistence`.
+fn add_count(counts: &mut Vec<BigUint>, persistence: usize, count: BigUint) {
+    if count.is_zero() {
+        return;
+    }
+    if counts.len() <= persistence {
+        counts.resize(persistence + 1, BigUint::zero());
+    }
+    counts[persistence] += count;
+}
+
+/// Number of limbs of low digits kept for each digit product.
+const LOW_LIMBS: usize = 4;
+
+/// The lowest digits of an integer, as little-endian limbs of `radix^limb_digits`.
+#[derive(Debug, Copy, Clone)]
+struct LowDigits {
+    limbs: [u64; LOW_LIMBS],
+    // Whether the integer has more digits than the limbs hold
+    truncated: bool,
+}
+
+impl LowDigits {
+    fn one() -> LowDigits {
+        let mut limbs = [0; LOW_LIMBS];
+        limbs[0] = 1;
+        LowDigits {
+            limbs,
+            truncated: false,
+        }
+    }
+
+    /// Multiply by a small factor, dropping anything that overflows the limbs.
+    fn multiply(&self, factor: u32, limb: u64) -> LowDigits {
+        let mut limbs = [0; LOW_LIMBS];
+        let mut carry: u128 = 0;
+        for (next_limb, limb_value) in limbs.iter_mut().zip(self.limbs.iter()) {
+            let value = u128::from(*limb_value) * u128::from(factor) + carry;
+            *next_limb = (value % u128::from(limb)) as u64;
+            carry = value / u128::from(limb);
+        }
+        LowDigits {
+            limbs,
+            truncated: self.truncated || carry > 0,
+        }
+    }
+
+    /// Return whether any digit we hold is a zero. Leading zeros of the limbs are only
+    /// digits of the integer when it was truncated.
+    fn has_zero(&self, radix: u32, limb_digits: usize) -> bool {
+        let radix = u64::from(radix);
+        let top = if self.truncated {
+            LOW_LIMBS - 1
+        } else {
+            self.limbs
+                .iter()
+                .rposition(|limb_value| *limb_value != 0)
+                .unwrap_or(0)
+        };
+        for (index, limb_value) in self.limbs.iter().enumerate().take(top + 1) {
+            let mut remaining = *limb_value;
+            for _ in 0..limb_digits {
+                if index == top && !self.truncated && remaining == 0 {
+                    break;
+                }
+                if remaining % radix == 0 {
+                    return true;
+                }
+                remaining /= radix;
+            }
+        }
+        false
+    }
+}
+
+/// Return the number of digits in a limb, and the limb itself, for low digit arithmetic.
+fn low_limb(radix: u32) -> (usize, u64) {
+    let mut limb_digits = 1;
+    let mut limb = u64::from(radix);
+    while let Some(next) = limb.checked_mul(u64::from(radix)) {
+        limb = next;
+        limb_digits += 1;
+    }
+    (limb_digits, limb)
+}
+
+/// A digit product built from shared digits, found by the shortest sequence giving it.
+struct SharedProduct {
+    shortest: usize,
+    low: LowDigits,
+}
+
+/// Everything needed to walk the digit products of integers up to some length.
+struct Products<'a> {
+    radix: u32,
+    limb_digits: usize,
+    limb: u64,
+    lonely: &'a [u32],
+    // Returns whether a product, as shared exponents then lonely counts, is divisible by the
+    // radix
+    divisible: &'a dyn Fn(&[u32], &[u32]) -> bool,
+}
+
+impl Products<'_> {
+    /// Collect every product of this shared product with lonely digits, in at most `budget`
+    /// more digits, that might not contain a zero digit.
+    fn lonely_products(
+        &self,
+        shared_exponents: &[u32],
+        lonely_counts: &mut Vec<u32>,
+        budget: usize,
+        low: LowDigits,
+        zero_free: &mut Vec<(Vec<u32>, Vec<u32>)>,
+    ) {
+        let index = lonely_counts.len();
+        if index == self.lonely.len() {
+            if !low.has_zero(self.radix, self.limb_digits) {
+                zero_free.push((shared_exponents.to_vec(), lonely_counts.clone()));
+            }
+            return;
+        }
+
+        let prime = self.lonely[index];
+        let mut low = low;
+        for count in 0..=budget {
+            lonely_counts.push(count as u32);
+            // Once divisible by the radix, more of this digit keeps it so
+            if (self.divisible)(shared_exponents, lonely_counts) {
+                lonely_counts.pop();
+                break;
+            }
+            self.lonely_products(
+                shared_exponents,
+                lonely_counts,
+                budget - count,
+                low,
+                zero_free,
+            );
+            lonely_counts.pop();
+            low = low.multiply(prime, self.limb);
+        }
+    }
+}
+
+/// For each product of shared digits that `wanted` products are built from, return the
+/// number of ordered sequences of each length giving it, up to `length` digits.
+fn sequence_counts(
+    wanted: impl Iterator<Item = Vec<u32>>,
+    shared_digits: &[Vec<u32>],
+    length: usize,
+) -> HashMap<Vec<u32>, Vec<BigUint>> {
+    // Everything a wanted product can be reached through, smallest first
+    let mut seen: HashSet<Vec<u32>> = wanted.collect();
+    let mut needed: Vec<Vec<u32>> = seen.iter().cloned().collect();
+    let mut index = 0;
+    while index < needed.len() {
+        for digit in shared_digits {
+            let product = &needed[index];
+            if product.iter().zip(digit).any(|(exponent, d)| exponent < d) {
+                continue;
+            }
+            let previous: Vec<u32> = product.iter().zip(digit).map(|(a, b)| a - b).collect();
+            if seen.insert(previous.clone()) {
+                needed.push(previous);
+            }
+        }
+        index += 1;
+    }
+    needed.sort_by_key(|product| product.iter().sum::<u32>());
+
+    let mut sequences: HashMap<Vec<u32>, Vec<BigUint>> = HashMap::new();
+    for product in needed {
+        let longest = (product.iter().sum::<u32>() as usize).min(length);
+        let mut counts = vec![BigUint::zero(); longest + 1];
+        if product.iter().all(Zero::is_zero) {
+            counts[0] = BigUint::one();
+        }
+        for digit in shared_digits {
+            if product.iter().zip(digit).any(|(exponent, d)| exponent < d) {
+                continue;
+            }
+            let previous: Vec<u32> = product.iter().zip(digit).map(|(a, b)| a - b).collect();
+            for (n, count) in sequences[&previous].iter().enumerate().take(longest) {
+                counts[n + 1] += count;
+            }
+        }
+        sequences.insert(product, counts);
+    }
+    sequences
+}
+
+/// Count the integers below `radix^length` with each multiplicative persistence value,
+/// without enumerating them. The result is indexed by persistence.
+///
+/// An integer's persistence depends only on its digit multiset, and digits of one do not
+/// change the digit product. Two shortcuts put almost every integer in a bucket without
+/// looking at it:
+///
+/// - integers containing a zero have a digit product of zero, so persistence 1
+/// - a digit product containing a zero, including one divisible by the radix, has
+///   persistence 1, so the integer has persistence 2
+///
+/// We walk each reachable digit product once, tracking only its lowest digits, and keep
+/// the few with no zero among them. For those, the integers giving that product are
+/// counted from their digit multisets by multinomial coefficients, and padding with ones
+/// to every length is summed as a single binomial.
+pub fn persistence_counts(length: usize, radix: u32) -> Vec<BigUint> {
+    let mut counts: Vec<BigUint> = Vec::new();
+
+    // Single digit integers, including zero, are already single digits
+    if length == 0 {
+        add_count(&mut counts, 0, BigUint::one());
+        return counts;
+    }
+    add_count(&mut counts, 0, BigUint::from(radix));
+    if length == 1 {
+        return counts;
+    }
+
+    // Totals of multi-digit integers with and without any zero digit
+    let radix_big = BigUint::from(radix);
+    let nonzero_digits = BigUint::from(radix - 1);
+    let mut with_zero = BigUint::zero();
+    let mut zero_free = BigUint::zero();
+    for digits in 2..=length {
+        let all: BigUint = &nonzero_digits * Pow::pow(&radix_big, digits - 1);
+        let without_zero: BigUint = Pow::pow(&nonzero_digits, digits);
+        with_zero += all - &without_zero;
+        zero_free += without_zero;
+    }
+    add_count(&mut counts, 1, with_zero);
+
+    // A prime with no other multiple below the radix only occurs as a digit of its own, so
+    // rather than following each sequence of those digits, we count their arrangements
+    let primes: Vec<u32> = (2..radix)
+        .filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+        .collect();
+    let (lonely, shared): (Vec<u32>, Vec<u32>) =
+        primes.iter().partition(|prime| 2 * **prime >= radix);
+    let (shared_digit_values, shared_digits): (Vec<u32>, Vec<Vec<u32>>) = (2..radix)
+        .filter(|digit| !lonely.contains(digit))
+        .map(|digit| {
+            let exponents =
+                prime_exponents(digit, &shared).expect("Digit has a lonely prime factor");
+            (digit, exponents)
+        })
+        .unzip();
+    // Products are kept as exponents of the shared primes then the lonely ones. A prime
+    // radix never divides a product of smaller digits.
+    let all_primes: Vec<u32> = shared.iter().chain(lonely.iter()).cloned().collect();
+    let radix_exponents = prime_exponents(radix, &all_primes);
+    let divisible = |shared_exponents: &[u32], lonely_counts: &[u32]| {
+        radix_exponents.as_ref().is_some_and(|radix_exponents| {
+            radix_exponents
+                .iter()
+                .zip(shared_exponents.iter().chain(lonely_counts))
+                .all(|(radix_exponent, exponent)| exponent >= radix_exponent)
+        })
+    };
+    let no_lonely = vec![0; lonely.len()];
+
+    // Every product of shared digits, breadth first so each is found by its shortest sequence
+    let (limb_digits, limb) = low_limb(radix);
+    let origin = vec![0; shared.len()];
+    let mut shared_products: HashMap<Vec<u32>, SharedProduct> = HashMap::new();
+    shared_products.insert(
+        origin.clone(),
+        SharedProduct {
+            shortest: 0,
+            low: LowDigits::one(),
+        },
+    );
+    let mut frontier = vec![origin];
+    for n in 1..=length {
+        let mut next_frontier = Vec::new();
+        for product in frontier {
+            let low = shared_products[&product].low;
+            for (digit, exponents) in shared_digit_values.iter().zip(shared_digits.iter()) {
+                let next_product: Vec<u32> =
+                    product.iter().zip(exponents).map(|(a, b)| a + b).collect();
+                if divisible(&next_product, &no_lonely)
+                    || shared_products.contains_key(&next_product)
+                {
+                    continue;
+                }
+                shared_products.insert(
+                    next_product.clone(),
+                    SharedProduct {
+                        shortest: n,
+                        low: low.multiply(*digit, limb),
+                    },
+                );
+                next_frontier.push(next_product);
+            }
+        }
+        frontier = next_frontier;
+    }
+
+    // Products that might not contain a zero digit, which need looking at in full
+    let products = Products {
+        radix,
+        limb_digits,
+        limb,
+        lonely: &lonely,
+        divisible: &divisible,
+    };
+    let mut zero_free_products = Vec::new();
+    for (exponents, shared_product) in shared_products.iter() {
+        products.lonely_products(
+            exponents,
+            &mut Vec::new(),
+            length - shared_product.shortest,
+            shared_product.low,
+            &mut zero_free_products,
+        );
+    }
+
+    let mut factorials = vec![BigUint::one()];
+    for n in 1..=length {
+        let factorial = &factorials[n - 1] * n;
+        factorials.push(factorial);
+    }
+    // Ways of placing a sequence of `n` digits among ones, over all lengths from two digits up
+    let placements: Vec<BigUint> = (0..=length)
+        .map(|n| binomial(length + 1, n + 1) - binomial(n.max(2), n + 1))
+        .collect();
+
+    // The exact persistence of each remaining product, leaving those with persistence 1
+    // with the rest below
+    let mut persistent_products = Vec::new();
+    for (shared_exponents, lonely_counts) in zero_free_products {
+        let product: BigUint = all_primes
+            .iter()
+            .zip(shared_exponents.iter().chain(lonely_counts.iter()))
+            .map(|(prime, exponent)| Pow::pow(&BigUint::from(*prime), *exponent))
+            .product();
+        let persistence = multiplicative_persistence_uint(&product, radix);
+        if persistence != 1 {
+            persistent_products.push((shared_exponents, lonely_counts, persistence));
+        }
+    }
+    let sequences = sequence_counts(
+        persistent_products
+            .iter()
+            .map(|(shared_exponents, _, _)| shared_exponents.clone()),
+        &shared_digits,
+        length,
+    );
+
+    let mut counted = BigUint::zero();
+    for (shared_exponents, lonely_counts, persistence) in persistent_products {
+        // Orders of all the digits other than one, over each length of shared sequence, then
+        // placements for the ones among them
+        let total: usize = lonely_counts.iter().map(|count| *count as usize).sum();
+        let lonely_orders: BigUint = lonely_counts
+            .iter()
+            .map(|count| &factorials[*count as usize])
+            .product();
+        let integers = sequences[&shared_exponents]
+            .iter()
+            .enumerate()
+            .take(length - total + 1)
+            .map(|(n, count)| {
+                count * &factorials[n + total] / &factorials[n] * &placements[n + total]
+            })
+            .sum::<BigUint>()
+            / lonely_orders;
+        counted += &integers;
+        add_count(&mut counts, persistence + 1, integers);
+    }
+
+    // Every other zero-free integer has a digit product containing a zero
+    add_count(&mut counts, 2, zero_free - counted);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::range::PersistenceRange;
+
+    /// Count persistence values the slow way, by listing every integer.
+    fn enumerate_counts(length: usize, radix: u32) -> Vec<BigUint> {
+        let mut counts = Vec::new();
+        for (_, persistence) in PersistenceRange::new(0, u64::from(radix).pow(length as u32), radix)
+        {
+            add_count(&mut counts, persistence, BigUint::one());
+        }
+        counts
+    }
+
+    #[test]
+    fn test_binomial() {
+        assert_eq!(binomial(0, 0), BigUint::from(1u32));
+        assert_eq!(binomial(5, 2), BigUint::from(10u32));
+        assert_eq!(binomial(5, 5), BigUint::from(1u32));
+        assert_eq!(binomial(2, 3), BigUint::from(0u32));
+    }
+
+    #[test]
+    fn test_persistence_counts() {
+        for radix in [3, 10, 12] {
+            for length in 0..=6 {
+                assert_eq!(
+                    persistence_counts(length, radix),
+                    enumerate_counts(length, radix)
+                );
+            }
+        }
+        for radix in 2..13 {
+            assert_eq!(persistence_counts(4, radix), enumerate_counts(4, radix));
+        }
+        // Too long to enumerate, but every integer is counted once
+        let total: BigUint = persistence_counts(120, 10).iter().sum();
+        assert_eq!(total, Pow::pow(&BigUint::from(10u32), 120u32));
+    }
+}
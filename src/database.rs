@@ -0,0 +1,296 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use num_bigint::BigUint;
+use num_traits::Num;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use super::CandidateDigits;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        operation TEXT NOT NULL,
+        radix INTEGER NOT NULL,
+        arguments TEXT NOT NULL,
+        started INTEGER NOT NULL,
+        finished INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS results (
+        radix INTEGER NOT NULL,
+        operation TEXT NOT NULL,
+        candidate TEXT NOT NULL,
+        persistence INTEGER NOT NULL,
+        run_id INTEGER NOT NULL REFERENCES runs (id),
+        PRIMARY KEY (radix, operation, candidate)
+    );
+    CREATE TABLE IF NOT EXISTS rounds (
+        id INTEGER PRIMARY KEY,
+        radix INTEGER NOT NULL,
+        digits TEXT NOT NULL,
+        round INTEGER NOT NULL,
+        milliseconds INTEGER NOT NULL,
+        run_id INTEGER NOT NULL REFERENCES runs (id),
+        UNIQUE (radix, digits, round)
+    );
+    CREATE TABLE IF NOT EXISTS round_results (
+        round_id INTEGER NOT NULL REFERENCES rounds (id),
+        position INTEGER NOT NULL,
+        candidate TEXT NOT NULL,
+        persistence INTEGER NOT NULL,
+        PRIMARY KEY (round_id, position)
+    );
+";
+
+/// Seconds since the Unix epoch.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Identify the candidates a search round is built from, so only identical rounds are skipped.
+fn digits_key(digits: &CandidateDigits) -> String {
+    format!(
+        "heads={} tail={} required={}",
+        digits.heads.join(","),
+        digits.tail.iter().collect::<String>(),
+        digits.required.iter().collect::<String>()
+    )
+}
+
+fn parse_candidate(candidate: String) -> BigUint {
+    Num::from_str_radix(&candidate, 10).expect("Invalid candidate in database")
+}
+
+fn insert_results<'a, I>(
+    connection: &Connection,
+    run_id: i64,
+    operation: &str,
+    radix: u32,
+    results: I,
+) -> Result<()>
+where
+    I: IntoIterator<Item = (&'a BigUint, usize)>,
+{
+    let mut statement = connection.prepare_cached(
+        "INSERT OR REPLACE INTO results (radix, operation, candidate, persistence, run_id)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (candidate, persistence) in results {
+        statement.execute(params![
+            radix,
+            operation,
+            candidate.to_string(),
+            persistence as i64,
+            run_id
+        ])?;
+    }
+    Ok(())
+}
+
+/// An SQLite file accumulating results, runs and search round timings across invocations.
+/// Candidates are stored in decimal.
+pub struct ResultsDatabase {
+    connection: Connection,
+}
+
+impl ResultsDatabase {
+    /// Open a database file, creating it and its tables if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ResultsDatabase> {
+        ResultsDatabase::new(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<ResultsDatabase> {
+        ResultsDatabase::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> Result<ResultsDatabase> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(ResultsDatabase { connection })
+    }
+
+    /// Record the start of a run, returning its id.
+    pub fn start_run(&self, operation: &str, radix: u32, arguments: &str) -> Result<i64> {
+        self.connection.execute(
+            "INSERT INTO runs (operation, radix, arguments, started) VALUES (?1, ?2, ?3, ?4)",
+            params![operation, radix, arguments, now()],
+        )?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    pub fn finish_run(&self, run_id: i64) -> Result<()> {
+        self.connection.execute(
+            "UPDATE runs SET finished = ?1 WHERE id = ?2",
+            params![now(), run_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record results together, replacing any earlier result for the same candidate.
+    pub fn add_results<'a, I>(
+        &mut self,
+        run_id: i64,
+        operation: &str,
+        radix: u32,
+        results: I,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = (&'a BigUint, usize)>,
+    {
+        let transaction = self.connection.transaction()?;
+        insert_results(&transaction, run_id, operation, radix, results)?;
+        transaction.commit()
+    }
+
+    /// Return the persistence recorded for a candidate, if any.
+    pub fn result(
+        &self,
+        operation: &str,
+        radix: u32,
+        candidate: &BigUint,
+    ) -> Result<Option<usize>> {
+        self.connection
+            .query_row(
+                "SELECT persistence FROM results
+                 WHERE radix = ?1 AND operation = ?2 AND candidate = ?3",
+                params![radix, operation, candidate.to_string()],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map(|persistence| persistence.map(|persistence| persistence as usize))
+    }
+
+    /// Record a completed search round, with everything it reported in order.
+    pub fn complete_round(
+        &mut self,
+        run_id: i64,
+        digits: &CandidateDigits,
+        round: usize,
+        milliseconds: u64,
+        results: &[(BigUint, usize)],
+    ) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+        insert_results(
+            &transaction,
+            run_id,
+            "search",
+            digits.radix,
+            results
+                .iter()
+                .map(|(candidate, persistence)| (candidate, *persistence)),
+        )?;
+        transaction.execute(
+            "INSERT INTO rounds (radix, digits, round, milliseconds, run_id)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                digits.radix,
+                digits_key(digits),
+                round as i64,
+                milliseconds as i64,
+                run_id
+            ],
+        )?;
+        let round_id = transaction.last_insert_rowid();
+        for (position, (candidate, persistence)) in results.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO round_results (round_id, position, candidate, persistence)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    round_id,
+                    position as i64,
+                    candidate.to_string(),
+                    *persistence as i64
+                ],
+            )?;
+        }
+        transaction.commit()
+    }
+
+    /// Return everything a completed search round reported, in order, or nothing if the
+    /// round has not been completed.
+    pub fn completed_round(
+        &self,
+        digits: &CandidateDigits,
+        round: usize,
+    ) -> Result<Option<Vec<(BigUint, usize)>>> {
+        let round_id: Option<i64> = self
+            .connection
+            .query_row(
+                "SELECT id FROM rounds WHERE radix = ?1 AND digits = ?2 AND round = ?3",
+                params![digits.radix, digits_key(digits), round as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let round_id = match round_id {
+            Some(round_id) => round_id,
+            None => return Ok(None),
+        };
+        let mut statement = self.connection.prepare(
+            "SELECT candidate, persistence FROM round_results
+             WHERE round_id = ?1 ORDER BY position",
+        )?;
+        let results = statement
+            .query_map(params![round_id], |row| {
+                Ok((parse_candidate(row.get(0)?), row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(results))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_results() {
+        let mut database = ResultsDatabase::open_in_memory().unwrap();
+        let run_id = database.start_run("list", 10, "list 38 40").unwrap();
+        database
+            .add_results(run_id, "list", 10, vec![(&big(38), 2), (&big(39), 3)])
+            .unwrap();
+        database.finish_run(run_id).unwrap();
+
+        assert_eq!(database.result("list", 10, &big(39)).unwrap(), Some(3));
+        assert_eq!(database.result("list", 12, &big(39)).unwrap(), None);
+        assert_eq!(database.result("search", 10, &big(39)).unwrap(), None);
+
+        // Later results replace earlier ones
+        database
+            .add_results(run_id, "list", 10, vec![(&big(39), 4)])
+            .unwrap();
+        assert_eq!(database.result("list", 10, &big(39)).unwrap(), Some(4));
+    }
+
+    #[test]
+    fn test_rounds() {
+        let mut database = ResultsDatabase::open_in_memory().unwrap();
+        let run_id = database.start_run("search", 10, "search").unwrap();
+        let digits = CandidateDigits::default();
+        let results = vec![(big(39), 3), (big(77), 4)];
+        database
+            .complete_round(run_id, &digits, 1, 5, &results)
+            .unwrap();
+
+        assert_eq!(database.completed_round(&digits, 1).unwrap(), Some(results));
+        assert_eq!(database.completed_round(&digits, 2).unwrap(), None);
+        assert_eq!(
+            database
+                .completed_round(&CandidateDigits::for_radix(12), 1)
+                .unwrap(),
+            None
+        );
+        assert_eq!(database.result("search", 10, &big(77)).unwrap(), Some(4));
+
+        // Empty rounds are still complete
+        database.complete_round(run_id, &digits, 2, 5, &[]).unwrap();
+        assert_eq!(database.completed_round(&digits, 2).unwrap(), Some(vec![]));
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::{round_size, CandidateDigits, SearchResult, SearchRound};
+
+/// A range of candidates in one search round, handed out to a worker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkUnit {
+    pub id: usize,
+    pub digits: CandidateDigits,
+    pub round: usize,
+    pub ranks: Range<u64>,
+}
+
+impl WorkUnit {
+    /// Write as a line of the protocol. Fields are separated by single spaces, and may be
+    /// empty, such as an empty head or no required digits.
+    fn to_line(&self) -> String {
+        format!(
+            "unit {} {} {} {} {} {} {} {}",
+            self.id,
+            self.round,
+            self.ranks.start,
+            self.ranks.end,
+            self.digits.radix,
+            self.digits.heads.join(","),
+            self.digits.tail.iter().collect::<String>(),
+            self.digits.required.iter().collect::<String>()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<WorkUnit> {
+        let fields: Vec<&str> = line.split(' ').collect();
+        match fields[..] {
+            ["unit", id, round, start, end, radix, heads, tail, required] => Some(WorkUnit {
+                id: id.parse().ok()?,
+                round: round.parse().ok()?,
+                ranks: start.parse().ok()?..end.parse().ok()?,
+                digits: CandidateDigits {
+                    radix: radix.parse().ok()?,
+                    heads: heads.split(',').map(String::from).collect(),
+                    tail: tail.chars().collect(),
+                    required: required.chars().collect(),
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Split search rounds into units of at most `unit_size` candidates, in search order. Rounds
+/// with too many candidates to count can't be split, and give an error.
+pub fn work_units(
+    radices: &[CandidateDigits],
+    from_round: usize,
+    num_rounds: usize,
+    unit_size: u64,
+) -> Result<Vec<WorkUnit>, String> {
+    let mut units = Vec::new();
+    for digits in radices.iter() {
+        for round in from_round..(from_round + num_rounds) {
+            let size = round_size(round, digits).ok_or_else(|| {
+                format!(
+                    "Round {} in radix {} has too many candidates to split into work units",
+                    round, digits.radix
+                )
+            })?;
+            let mut start = 0;
+            while start < size {
+                let end = size.min(start + unit_size);
+                units.push(WorkUnit {
+                    id: units.len(),
+                    digits: digits.clone(),
+                    round,
+                    ranks: start..end,
+                });
+                start = end;
+            }
+        }
+    }
+    Ok(units)
+}
+
+/// Work units waiting to be handed out, and the results of those completed.
+struct Schedule {
+    pending: VecDeque<usize>,
+    results: Vec<Option<Vec<SearchResult>>>,
+    completed: usize,
+}
+
+/// Read a worker's results for a unit, up to the line saying it is done.
+fn read_results<R: BufRead>(reader: &mut R, id: usize) -> io::Result<Vec<SearchResult>> {
+    let invalid = |line: &str| io::Error::new(ErrorKind::InvalidData, line.to_owned());
+    let mut results = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let fields: Vec<&str> = line.trim_end().split(' ').collect();
+        match fields[..] {
+            ["result", unit, persistence, candidate] if unit == id.to_string() => {
+                results.push(SearchResult {
+                    candidate: candidate.to_owned(),
+                    multiplicative_persistence: persistence.parse().map_err(|_| invalid(&line))?,
+                })
+            }
+            ["done", unit] if unit == id.to_string() => return Ok(results),
+            _ => return Err(invalid(&line)),
+        }
+    }
+}
+
+/// Hand out work units to one worker until there are none left, putting back any unit
+/// the worker does not finish, including when it goes quiet for longer than `unit_timeout`.
+fn serve_worker(
+    stream: TcpStream,
+    units: &[WorkUnit],
+    schedule: &(Mutex<Schedule>, Condvar),
+    unit_timeout: Option<Duration>,
+) {
+    if stream.set_read_timeout(unit_timeout).is_err() {
+        return;
+    }
+    let (lock, condvar) = schedule;
+    let mut reader = match stream.try_clone() {
+        Ok(stream) => BufReader::new(stream),
+        Err(_) => return,
+    };
+    let mut writer = stream;
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(_) if line.trim_end() == "ready" => {}
+        _ => return,
+    }
+
+    loop {
+        let index = {
+            let mut schedule = lock.lock().unwrap();
+            loop {
+                if let Some(index) = schedule.pending.pop_front() {
+                    break Some(index);
+                }
+                if schedule.completed == units.len() {
+                    break None;
+                }
+                // Other workers might still disconnect and give their units back
+                schedule = condvar.wait(schedule).unwrap();
+            }
+        };
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let _ = writeln!(writer, "exit");
+                return;
+            }
+        };
+
+        let unit = &units[index];
+        let results =
+            writeln!(writer, "{}", unit.to_line()).and_then(|_| read_results(&mut reader, unit.id));
+        let mut schedule = lock.lock().unwrap();
+        match results {
+            Ok(results) => {
+                schedule.results[index] = Some(results);
+                schedule.completed += 1;
+                condvar.notify_all();
+            }
+            Err(_) => {
+                schedule.pending.push_front(index);
+                condvar.notify_all();
+                return;
+            }
+        }
+    }
+}
+
+/// Hand out work units to workers connecting to `listener`, and pass each unit with its
+/// results to `on_unit` in order. Units are given to another worker if a worker disconnects
+/// before finishing them, or sends nothing for `unit_timeout`. Returns once every unit is done.
+pub fn coordinate<F>(
+    listener: TcpListener,
+    units: Vec<WorkUnit>,
+    unit_timeout: Option<Duration>,
+    mut on_unit: F,
+) -> io::Result<()>
+where
+    F: FnMut(&WorkUnit, Vec<SearchResult>),
+{
+    let units = Arc::new(units);
+    let schedule = Arc::new((
+        Mutex::new(Schedule {
+            pending: (0..units.len()).collect(),
+            results: (0..units.len()).map(|_| None).collect(),
+            completed: 0,
+        }),
+        Condvar::new(),
+    ));
+    let finished = Arc::new(AtomicBool::new(false));
+
+    // Accept without blocking, so we can stop when finished
+    listener.set_nonblocking(true)?;
+    let acceptor = {
+        let units = units.clone();
+        let schedule = schedule.clone();
+        let finished = finished.clone();
+        thread::spawn(move || {
+            while !finished.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if stream.set_nonblocking(false).is_err() {
+                            continue;
+                        }
+                        let units = units.clone();
+                        let schedule = schedule.clone();
+                        thread::spawn(move || {
+                            serve_worker(stream, &units, &schedule, unit_timeout)
+                        });
+                    }
+                    Err(_) => thread::sleep(Duration::from_millis(20)),
+                }
+            }
+        })
+    };
+
+    let (lock, condvar) = &*schedule;
+    for (index, unit) in units.iter().enumerate() {
+        let results = {
+            let mut schedule = lock.lock().unwrap();
+            loop {
+                if let Some(results) = schedule.results[index].take() {
+                    break results;
+                }
+                schedule = condvar.wait(schedule).unwrap();
+            }
+        };
+        on_unit(unit, results);
+    }
+
+    finished.store(true, Ordering::SeqCst);
+    acceptor.join().expect("Acceptor thread panicked");
+    Ok(())
+}
+
+/// Connect to a coordinator and search the work units it hands out until it has no more,
+/// returning how many units we completed.
+pub fn work<A: ToSocketAddrs>(address: A) -> io::Result<usize> {
+    let stream = TcpStream::connect(address)?;
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    writeln!(writer, "ready")?;
+    writer.flush()?;
+
+    // Every line before the last hands out a unit
+    for (completed, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line == "exit" {
+            return Ok(completed);
+        }
+        let unit = WorkUnit::from_line(&line)
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, line.clone()))?;
+        for result in SearchRound::ranked(unit.round, &unit.digits, unit.ranks.clone()) {
+            writeln!(
+                writer,
+                "result {} {} {}",
+                unit.id, result.multiplicative_persistence, result.candidate
+            )?;
+        }
+        writeln!(writer, "done {}", unit.id)?;
+        writer.flush()?;
+    }
+    Err(io::Error::new(
+        ErrorKind::UnexpectedEof,
+        "Coordinator disconnected",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Shutdown;
+
+    #[test]
+    fn test_work_unit_line() {
+        for digits in [
+            CandidateDigits::default(),
+            CandidateDigits::for_radix(12),
+            CandidateDigits::restricted(10, &['2', '3', '8'], &['3']),
+        ]
+        .iter()
+        {
+            let unit = WorkUnit {
+                id: 3,
+                digits: digits.clone(),
+                round: 20,
+                ranks: 100..200,
+            };
+            assert_eq!(WorkUnit::from_line(&unit.to_line()), Some(unit));
+        }
+        assert_eq!(WorkUnit::from_line("unit 3"), None);
+    }
+
+    #[test]
+    fn test_work_units() {
+        let digits = CandidateDigits::default();
+        let units = work_units(std::slice::from_ref(&digits), 0, 6, 50).unwrap();
+        for round in 0..6 {
+            let ranks: Vec<Range<u64>> = units
+                .iter()
+                .filter(|unit| unit.round == round)
+                .map(|unit| unit.ranks.clone())
+                .collect();
+            let size = round_size(round, &digits).unwrap();
+            assert_eq!(
+                ranks
+                    .iter()
+                    .map(|ranks| ranks.end - ranks.start)
+                    .sum::<u64>(),
+                size
+            );
+            assert!(ranks.iter().all(|ranks| ranks.end - ranks.start <= 50));
+        }
+        assert!(units.iter().enumerate().all(|(i, unit)| unit.id == i));
+
+        assert!(work_units(&[CandidateDigits::for_radix(36)], 37, 1, 50).is_err());
+    }
+
+    #[test]
+    fn test_coordinate() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let units = work_units(&[CandidateDigits::default()], 0, 8, 40).unwrap();
+        let expected: Vec<Vec<SearchResult>> = units
+            .iter()
+            .map(|unit| SearchRound::ranked(unit.round, &unit.digits, unit.ranks.clone()).collect())
+            .collect();
+
+        let workers = thread::spawn(move || {
+            // One worker disconnects partway through its first unit
+            let stream = TcpStream::connect(address).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            writeln!(&stream, "ready").unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(line.starts_with("unit 0 "));
+            writeln!(&stream, "result 0 3 39").unwrap();
+            stream.shutdown(Shutdown::Both).unwrap();
+
+            // Another takes the unit back up, but then stays connected without answering
+            let silent = TcpStream::connect(address).unwrap();
+            let mut reader = BufReader::new(silent.try_clone().unwrap());
+            writeln!(&silent, "ready").unwrap();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(line.starts_with("unit 0 "));
+
+            let others: Vec<thread::JoinHandle<usize>> = (0..2)
+                .map(|_| thread::spawn(move || work(address).unwrap()))
+                .collect();
+            let completed = others
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .sum::<usize>();
+            (completed, silent)
+        });
+
+        let mut merged: Vec<(usize, Vec<SearchResult>)> = Vec::new();
+        coordinate(
+            listener,
+            units.clone(),
+            Some(Duration::from_secs(1)),
+            |unit, results| merged.push((unit.id, results)),
+        )
+        .unwrap();
+
+        let (completed, _silent) = workers.join().unwrap();
+        assert_eq!(completed, units.len());
+        let ids: Vec<usize> = merged.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, (0..units.len()).collect::<Vec<usize>>());
+        let results: Vec<Vec<SearchResult>> =
+            merged.into_iter().map(|(_, results)| results).collect();
+        assert_eq!(results, expected);
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use num_bigint::BigUint;
+
+use super::{to_digits, to_notation};
+
+/// One step of the digit product chain.
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    pub value: BigUint,
+    pub digits: Vec<u32>,
+    // How many times each digit occurs
+    pub digit_counts: BTreeMap<u32, usize>,
+    pub product: BigUint,
+    // Prime factors of a non-zero product, with their exponents
+    pub factorisation: BTreeMap<u32, usize>,
+}
+
+/// The chain of digit products from an integer down to a single digit.
+#[derive(Debug, PartialEq)]
+pub struct Explanation {
+    pub radix: u32,
+    pub steps: Vec<Step>,
+    pub final_digit: BigUint,
+}
+
+/// Return the prime factors of a positive integer, with their exponents.
+fn factorise(mut n: u32) -> BTreeMap<u32, usize> {
+    let mut factors = BTreeMap::new();
+    let mut factor = 2;
+    while factor <= n / factor {
+        while n.is_multiple_of(factor) {
+            *factors.entry(factor).or_default() += 1;
+            n /= factor;
+        }
+        factor += 1;
+    }
+    if n > 1 {
+        *factors.entry(n).or_default() += 1;
+    }
+    factors
+}
+
+/// Follow the digit products of an integer down to a single digit.
+pub fn explain(candidate: &BigUint, radix: u32) -> Explanation {
+    let mut steps = Vec::new();
+    let mut value = candidate.clone();
+    while value >= BigUint::from(radix) {
+        let digits = to_digits(&value, radix);
+        let mut digit_counts = BTreeMap::new();
+        for digit in digits.iter() {
+            *digit_counts.entry(*digit).or_default() += 1;
+        }
+        let product: BigUint = digits.iter().map(|digit| BigUint::from(*digit)).product();
+
+        let mut factorisation = BTreeMap::new();
+        if !digit_counts.contains_key(&0) {
+            for (digit, count) in digit_counts.iter() {
+                for (prime, exponent) in factorise(*digit) {
+                    *factorisation.entry(prime).or_default() += exponent * count;
+                }
+            }
+        }
+
+        steps.push(Step {
+            value,
+            digits,
+            digit_counts,
+            product: product.clone(),
+            factorisation,
+        });
+        value = product;
+    }
+    Explanation {
+        radix,
+        steps,
+        final_digit: value,
+    }
+}
+
+impl Explanation {
+    pub fn multiplicative_persistence(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Write an integer in decimal, and also natively outside radix 10.
+    fn format_value(&self, value: &BigUint) -> String {
+        if self.radix == 10 {
+            value.to_string()
+        } else {
+            format!("{} ({})", value, to_notation(value, self.radix))
+        }
+    }
+
+    fn format_chain(&self) -> String {
+        let values: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| step.value.to_string())
+            .chain(Some(self.final_digit.to_string()))
+            .collect();
+        values.join(" → ")
+    }
+
+    /// Render as plain text, one block per step.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "{}", self.format_chain()).unwrap();
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(out, "step {}: {}", i + 1, self.format_value(&step.value)).unwrap();
+            writeln!(
+                out,
+                "  {} = {}",
+                format_digits(&step.digits),
+                self.format_value(&step.product)
+            )
+            .unwrap();
+            writeln!(out, "  digit counts: {}", format_counts(&step.digit_counts)).unwrap();
+            writeln!(out, "  factorisation: {}", format_factorisation(step)).unwrap();
+        }
+        writeln!(out, "final digit: {}", self.final_digit).unwrap();
+        writeln!(
+            out,
+            "multiplicative persistence: {}",
+            self.multiplicative_persistence()
+        )
+        .unwrap();
+        out
+    }
+
+    /// Render as Markdown, with a table of steps.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "**{}**", self.format_chain()).unwrap();
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "| Step | Value | Digit product | Digit counts | Factorisation |"
+        )
+        .unwrap();
+        writeln!(out, "| --- | --- | --- | --- | --- |").unwrap();
+        for (i, step) in self.steps.iter().enumerate() {
+            writeln!(
+                out,
+                "| {} | {} | {} = {} | {} | {} |",
+                i + 1,
+                self.format_value(&step.value),
+                format_digits(&step.digits),
+                step.product,
+                format_counts(&step.digit_counts),
+                format_factorisation(step)
+            )
+            .unwrap();
+        }
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "Final digit **{}**, multiplicative persistence **{}**.",
+            self.final_digit,
+            self.multiplicative_persistence()
+        )
+        .unwrap();
+        out
+    }
+}
+
+fn format_digits(digits: &[u32]) -> String {
+    let digits: Vec<String> = digits.iter().map(|digit| digit.to_string()).collect();
+    digits.join(" × ")
+}
+
+fn format_counts(counts: &BTreeMap<u32, usize>) -> String {
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(digit, count)| format!("{}:{}", digit, count))
+        .collect();
+    counts.join(", ")
+}
+
+fn format_factorisation(step: &Step) -> String {
+    if step.factorisation.is_empty() {
+        // Either zero, or one with no prime factors
+        return step.product.to_string();
+    }
+    let factors: Vec<String> = step
+        .factorisation
+        .iter()
+        .map(|(prime, exponent)| match exponent {
+            1 => prime.to_string(),
+            _ => format!("{}^{}", prime, exponent),
+        })
+        .collect();
+    factors.join("·")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_factorise() {
+        let factors: Vec<(u32, usize)> = factorise(360).into_iter().collect();
+        assert_eq!(factors, vec![(2, 3), (3, 2), (5, 1)]);
+        assert!(factorise(1).is_empty());
+        assert_eq!(factorise(4_294_967_291).len(), 1);
+    }
+
+    #[test]
+    fn test_explain() {
+        let explanation = explain(&big(77), 10);
+        assert_eq!(explanation.multiplicative_persistence(), 4);
+        assert_eq!(explanation.final_digit, big(8));
+        let values: Vec<BigUint> = explanation.steps.iter().map(|s| s.value.clone()).collect();
+        assert_eq!(values, vec![big(77), big(49), big(36), big(18)]);
+        let factors: Vec<(u32, usize)> = explanation.steps[1]
+            .factorisation
+            .clone()
+            .into_iter()
+            .collect();
+        assert_eq!(factors, vec![(2, 2), (3, 2)]);
+
+        let explanation = explain(&big(5), 10);
+        assert!(explanation.steps.is_empty());
+        assert_eq!(explanation.final_digit, big(5));
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(
+            explain(&big(25), 10).to_text(),
+            "25 → 10 → 0\n\
+             step 1: 25\n  2 × 5 = 10\n  digit counts: 2:1, 5:1\n  factorisation: 2·5\n\
+             step 2: 10\n  1 × 0 = 0\n  digit counts: 0:1, 1:1\n  factorisation: 0\n\
+             final digit: 0\nmultiplicative persistence: 2\n"
+        );
+        assert!(explain(&big(255), 16)
+            .to_text()
+            .contains("step 1: 255 (ff)\n  15 × 15 = 225 (e1)\n"));
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        assert_eq!(
+            explain(&big(39), 10).to_markdown(),
+            "**39 → 27 → 14 → 4**\n\n\
+             | Step | Value | Digit product | Digit counts | Factorisation |\n\
+             | --- | --- | --- | --- | --- |\n\
+             | 1 | 39 | 3 × 9 = 27 | 3:1, 9:1 | 3^3 |\n\
+             | 2 | 27 | 2 × 7 = 14 | 2:1, 7:1 | 2·7 |\n\
+             | 3 | 14 | 1 × 4 = 4 | 1:1, 4:1 | 2^2 |\n\n\
+             Final digit **4**, multiplicative persistence **3**.\n"
+        );
+    }
+}
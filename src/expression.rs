@@ -0,0 +1,323 @@
+use std::error::Error;
+use std::fmt;
+
+use num_bigint::BigUint;
+use num_traits::{Num, One, Pow, ToPrimitive, Zero};
+
+/// Why an expression could not be evaluated, and where.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionError {
+    // Byte offset into the expression
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl Error for ExpressionError {}
+
+type Result<T> = std::result::Result<T, ExpressionError>;
+
+/// Most decimal digits an expression or any part of it may have, so that a short expression
+/// can't ask for more memory than we have.
+pub const MAX_DIGITS: usize = 100_000;
+
+/// Return roughly how many decimal digits `base^exponent` has.
+fn power_digits(base: &BigUint, exponent: u32) -> f64 {
+    // Keep the leading bits of the base for its logarithm, which is all a float can hold
+    let shift = base.bits().saturating_sub(64);
+    let leading = (base >> shift)
+        .to_f64()
+        .expect("Leading bits are not a float");
+    (leading.log10() + shift as f64 * std::f64::consts::LOG10_2) * f64::from(exponent)
+}
+
+/// Evaluate an arithmetic expression over non-negative integers.
+///
+/// Supports `+`, `-`, `*`, `^` (right associative) and parentheses, with the functions
+/// `repunit(n)`, the integer of n ones, and `rep(a, n)`, the decimal digits of a repeated
+/// n times. Terms written next to each other with no space have their decimal digits
+/// joined, so `rep(7,6)8` is 7777778. Results with more than `MAX_DIGITS` digits are
+/// refused before they are worked out.
+pub fn evaluate(expression: &str) -> Result<BigUint> {
+    let mut parser = Parser {
+        input: expression.as_bytes(),
+        position: 0,
+    };
+    let value = parser.sum()?;
+    parser.skip_whitespace();
+    if parser.position < parser.input.len() {
+        return Err(parser.error("Unexpected character"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> ExpressionError {
+        ExpressionError {
+            position: self.position,
+            message: message.to_owned(),
+        }
+    }
+
+    fn too_large(&self, position: usize) -> ExpressionError {
+        ExpressionError {
+            position,
+            message: format!("Result has more than {} digits", MAX_DIGITS),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    /// Consume the next non-whitespace character if it is `expected`.
+    fn accept(&mut self, expected: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        if self.accept(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", expected as char)))
+        }
+    }
+
+    fn sum(&mut self) -> Result<BigUint> {
+        let mut value = self.product()?;
+        loop {
+            if self.accept(b'+') {
+                value += self.product()?;
+            } else if self.accept(b'-') {
+                let start = self.position;
+                let subtrahend = self.product()?;
+                if subtrahend > value {
+                    return Err(ExpressionError {
+                        position: start,
+                        message: "Result is negative".to_owned(),
+                    });
+                }
+                value -= subtrahend;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<BigUint> {
+        let mut value = self.power()?;
+        while self.accept(b'*') {
+            let start = self.position;
+            let factor = self.power()?;
+            let bits = value.bits() + factor.bits();
+            if bits as f64 * std::f64::consts::LOG10_2 > MAX_DIGITS as f64 {
+                return Err(self.too_large(start));
+            }
+            value *= factor;
+        }
+        Ok(value)
+    }
+
+    fn power(&mut self) -> Result<BigUint> {
+        let base = self.concatenation()?;
+        if !self.accept(b'^') {
+            return Ok(base);
+        }
+        let start = self.position;
+        let exponent = self.power()?;
+        if exponent.is_zero() {
+            return Ok(BigUint::one());
+        }
+        match exponent.to_u32() {
+            Some(_) | None if base <= BigUint::one() => Ok(base),
+            Some(exponent) if power_digits(&base, exponent) > MAX_DIGITS as f64 => {
+                Err(self.too_large(start))
+            }
+            Some(exponent) => Ok(Pow::pow(&base, exponent)),
+            None => Err(ExpressionError {
+                position: start,
+                message: "Exponent is too large".to_owned(),
+            }),
+        }
+    }
+
+    /// Join the decimal digits of adjacent terms.
+    fn concatenation(&mut self) -> Result<BigUint> {
+        self.skip_whitespace();
+        let mut value = self.term()?;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'(')
+        {
+            let start = self.position;
+            let mut digits = value.to_string();
+            digits.push_str(&self.term()?.to_string());
+            if digits.len() > MAX_DIGITS {
+                return Err(self.too_large(start));
+            }
+            value = Num::from_str_radix(&digits, 10).expect("Joined digits are not an integer");
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<BigUint> {
+        let start = self.position;
+        match self.peek() {
+            Some(b'(') => {
+                self.position += 1;
+                let value = self.sum()?;
+                self.expect(b')')?;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.position += 1;
+                }
+                let digits = std::str::from_utf8(&self.input[start..self.position])
+                    .expect("Digits are not UTF-8");
+                Ok(Num::from_str_radix(digits, 10).expect("Digits are not an integer"))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                    self.position += 1;
+                }
+                let name = std::str::from_utf8(&self.input[start..self.position])
+                    .expect("Name is not UTF-8");
+                self.function(name, start)
+            }
+            _ => Err(self.error("Expected an integer")),
+        }
+    }
+
+    fn function(&mut self, name: &str, start: usize) -> Result<BigUint> {
+        self.expect(b'(')?;
+        let (digits, count) = match name {
+            "repunit" => ("1".to_owned(), self.count()?),
+            "rep" => {
+                let digits = self.sum()?.to_string();
+                self.expect(b',')?;
+                (digits, self.count()?)
+            }
+            _ => {
+                return Err(ExpressionError {
+                    position: start,
+                    message: format!("Unknown function '{}'", name),
+                })
+            }
+        };
+        self.expect(b')')?;
+        if count == 0 {
+            return Ok(BigUint::zero());
+        }
+        if digits
+            .len()
+            .checked_mul(count)
+            .is_none_or(|length| length > MAX_DIGITS)
+        {
+            return Err(self.too_large(start));
+        }
+        Ok(Num::from_str_radix(&digits.repeat(count), 10)
+            .expect("Repeated digits are not an integer"))
+    }
+
+    /// Parse a count of repeats.
+    fn count(&mut self) -> Result<usize> {
+        self.skip_whitespace();
+        let start = self.position;
+        self.sum()?.to_usize().ok_or(ExpressionError {
+            position: start,
+            message: "Count is too large".to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn pow(base: u32, exponent: u32) -> BigUint {
+        Pow::pow(&BigUint::from(base), exponent)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(evaluate("277777788888899"), Ok(big(277_777_788_888_899)));
+        assert_eq!(evaluate("2^100*3^50"), Ok(pow(2, 100) * pow(3, 50)));
+        assert_eq!(evaluate("10^233-1"), Ok(pow(10, 233) - big(1)));
+        assert_eq!(
+            evaluate("repunit(19)*7"),
+            Ok(big(1_111_111_111_111_111_111) * big(7))
+        );
+        assert_eq!(evaluate("rep(7,6)8"), Ok(big(7_777_778)));
+        assert_eq!(
+            evaluate("2rep(7, 6)rep(8,6)99"),
+            Ok(big(277_777_788_888_899))
+        );
+
+        // Precedence and associativity
+        assert_eq!(evaluate("1 + 2 * 3 ^ 2"), Ok(big(19)));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(big(9)));
+        assert_eq!(evaluate("2^3^2"), Ok(big(512)));
+        assert_eq!(evaluate("10 - 2 - 3"), Ok(big(5)));
+        assert_eq!(evaluate("rep(12,0)"), Ok(big(0)));
+        assert_eq!(evaluate("0^0"), Ok(big(1)));
+        assert_eq!(evaluate("0^1"), Ok(big(0)));
+        assert_eq!(evaluate("1^(10^30)"), Ok(big(1)));
+        assert_eq!(evaluate("0^(10^30)"), Ok(big(0)));
+        assert_eq!(evaluate("10^99999").unwrap().to_string().len(), MAX_DIGITS);
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        let error = |expression: &str| evaluate(expression).unwrap_err();
+        assert_eq!(error("").position, 0);
+        assert_eq!(error("2 - 3").message, "Result is negative");
+        assert_eq!(error("2 +").position, 3);
+        assert_eq!(error("(2 + 3").message, "Expected ')'");
+        assert_eq!(error("2 3").message, "Unexpected character");
+        assert_eq!(error("fact(3)").message, "Unknown function 'fact'");
+        assert_eq!(error("2^(2^40)").message, "Exponent is too large");
+        assert_eq!(
+            error("10^4000000000").message,
+            "Result has more than 100000 digits"
+        );
+        assert_eq!(error("10^4000000000").position, 3);
+        assert_eq!(error("rep(7,10^12)").position, 0);
+        assert_eq!(
+            error("repunit(100001)").message,
+            error("10^99999*10^2").message
+        );
+        assert_eq!(error("rep(7,60000)rep(7,60000)").position, 12);
+        assert_eq!(
+            error("2 $ 3").to_string(),
+            "Unexpected character at position 2"
+        );
+    }
+}
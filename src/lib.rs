@@ -1,74 +1,304 @@
+extern crate cairo;
+extern crate memmap;
 extern crate num_bigint;
 extern crate num_traits;
+extern crate rusqlite;
+
+use std::collections::HashSet;
+use std::ops::Range;
 
 use num_bigint::BigUint;
-use num_traits::Num;
+use num_traits::{Num, ToPrimitive, Zero};
 
+pub mod arrangements;
 pub mod combinations_wr;
-use combinations_wr::CombinationsWithReplacement;
+pub mod count;
+pub mod database;
+pub mod distributed;
+pub mod explain;
+pub mod expression;
+pub mod multibase;
+pub mod numeral;
+pub mod oeis;
+pub mod permutations_ms;
+pub mod preimage;
+pub mod prime;
+pub mod range;
+pub mod records;
+pub mod sample;
+pub mod sequence;
+pub mod server;
+pub mod smooth;
+pub mod stats;
+pub mod table;
+pub mod tree;
+pub mod verify;
+pub mod visualize;
+use combinations_wr::{count_combinations, CombinationsWithReplacement};
+use numeral::{NumeralSystem, Standard};
 
-static DIGITS_HEAD: &'static [&'static str; 4] = &["", "2", "3", "4"];
-static DIGITS_TAIL: &'static [char; 4] = &['6', '7', '8', '9'];
+/// Return the digits of an integer, most significant first.
+pub fn to_digits(a: &BigUint, radix: u32) -> Vec<u32> {
+    if radix <= 256 {
+        return a.to_radix_be(radix).into_iter().map(u32::from).collect();
+    }
+    let mut digits = Vec::new();
+    let mut rest = a.clone();
+    loop {
+        digits.push((&rest % radix).to_u32().expect("Digit larger than radix"));
+        rest /= radix;
+        if rest.is_zero() {
+            break;
+        }
+    }
+    digits.reverse();
+    digits
+}
+
+/// Return the integer with the given digits, most significant first.
+pub fn from_digits(digits: &[u32], radix: u32) -> BigUint {
+    digits
+        .iter()
+        .fold(BigUint::from(0u32), |acc, digit| acc * radix + *digit)
+}
+
+/// Write an integer in the notation of a radix. Up to radix 36 this uses the usual
+/// alphanumeric digits, and beyond that decimal digit values separated by colons,
+/// such as `1:0:59` in radix 60.
+pub fn to_notation(a: &BigUint, radix: u32) -> String {
+    if radix <= 36 {
+        return a.to_str_radix(radix);
+    }
+    let digits: Vec<String> = to_digits(a, radix)
+        .iter()
+        .map(|digit| digit.to_string())
+        .collect();
+    digits.join(":")
+}
+
+/// Read an integer written in the notation of a radix, as given by `to_notation`.
+pub fn from_notation(notation: &str, radix: u32) -> Option<BigUint> {
+    if radix <= 36 {
+        return Num::from_str_radix(notation, radix).ok();
+    }
+    let mut digits = Vec::new();
+    for digit in notation.split(':') {
+        match digit.parse() {
+            Ok(digit) if digit < radix => digits.push(digit),
+            _ => return None,
+        }
+    }
+    Some(from_digits(&digits, radix))
+}
 
 /// Multiply digits of an integer together and return the result.
-fn multiply_digits(a: &BigUint, radix: u32) -> BigUint {
-    a.to_str_radix(radix)
-        .chars()
-        .map(|c| BigUint::from(c.to_digit(radix).expect("Could not convert char to digit.")))
-        .product()
+fn multiply_digits<S: NumeralSystem + ?Sized>(a: &BigUint, system: &S) -> BigUint {
+    system.digits(a).into_iter().map(BigUint::from).product()
 }
 
 /// Return the multiplicative persistence of a positive integer given as a string.
 pub fn multiplicative_persistence(candidate: &str, radix: u32) -> usize {
-    let mut derived_int: BigUint =
+    let derived_int: BigUint =
         Num::from_str_radix(candidate, 10).expect("Could not convert candidate to BigUint");
+    multiplicative_persistence_uint(&derived_int, radix)
+}
 
+/// Return the multiplicative persistence of a positive integer.
+pub fn multiplicative_persistence_uint(candidate: &BigUint, radix: u32) -> usize {
+    // Digit products of multi-digit integers always shrink, so this always finishes
+    let system = Standard(radix);
+    let mut derived_int = candidate.clone();
     let mut counter: usize = 0;
-    let stop_after = BigUint::from(radix);
-    while derived_int >= stop_after {
-        derived_int = multiply_digits(&derived_int, radix);
+    while !system.is_single_digit(&derived_int) {
+        derived_int = multiply_digits(&derived_int, &system);
         counter += 1;
     }
     counter
 }
 
+/// Return the multiplicative persistence of a positive integer written in any numeral system,
+/// or nothing if its digit products loop without reaching a single digit. In negative bases
+/// a digit product can be larger than the integer, such as 26 to 48 to 48 in negadecimal.
+pub fn multiplicative_persistence_in<S: NumeralSystem + ?Sized>(
+    candidate: &BigUint,
+    system: &S,
+) -> Option<usize> {
+    let mut derived_int = candidate.clone();
+    let mut seen: HashSet<BigUint> = HashSet::new();
+    let mut counter: usize = 0;
+    while !system.is_single_digit(&derived_int) {
+        if !seen.insert(derived_int.clone()) {
+            return None;
+        }
+        derived_int = multiply_digits(&derived_int, system);
+        counter += 1;
+    }
+    Some(counter)
+}
+
 #[derive(Debug, PartialEq)]
 pub struct SearchResult {
+    // Written in the radix searched
     pub candidate: String,
     pub multiplicative_persistence: usize,
 }
 
+/// The digits search candidates are built from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateDigits {
+    // The radix candidates are written in
+    pub radix: u32,
+    // Optional digits (or empty string) at the start of each candidate
+    pub heads: Vec<String>,
+    // Digits following the head, in ascending order
+    pub tail: Vec<char>,
+    // Digits every candidate must contain
+    pub required: Vec<char>,
+}
+
+impl Default for CandidateDigits {
+    /// The digits for searching radix 10. A 0 gives a product of zero and a 1 can be
+    /// removed, while any pair of 2, 3 and 4 can be replaced by fewer or smaller digits
+    /// with the same product. 5s are skipped, as alongside any even digit they give a
+    /// product ending in zero.
+    fn default() -> CandidateDigits {
+        CandidateDigits {
+            radix: 10,
+            heads: vec!["", "2", "3", "4"]
+                .into_iter()
+                .map(|s| s.to_owned())
+                .collect(),
+            tail: vec!['6', '7', '8', '9'],
+            required: Vec::new(),
+        }
+    }
+}
+
+impl CandidateDigits {
+    /// The digits for searching any radix, using the default pruning in radix 10.
+    ///
+    /// Otherwise we rely only on any two digits with a product below the radix being
+    /// replaceable by a single digit, which gives a smaller integer with the same product.
+    /// So a record has at most one digit whose square is below the radix, as its head.
+    pub fn for_radix(radix: u32) -> CandidateDigits {
+        if radix == 10 {
+            return CandidateDigits::default();
+        }
+        let digit_char = |digit| std::char::from_digit(digit, radix).expect("Invalid digit");
+        let (small, large): (Vec<u32>, Vec<u32>) =
+            (2..radix).partition(|digit| digit * digit < radix);
+        CandidateDigits {
+            radix,
+            heads: std::iter::once(String::new())
+                .chain(small.into_iter().map(|digit| digit_char(digit).to_string()))
+                .collect(),
+            tail: large.into_iter().map(digit_char).collect(),
+            required: Vec::new(),
+        }
+    }
+
+    /// Build candidates from `allowed` digits only, each containing all `required` digits.
+    ///
+    /// The default pruning relies on replacing digits with others, which may not be allowed,
+    /// so we only skip digits that can never appear in a record: a 0 gives a product of zero,
+    /// and a 1 could be removed to give a smaller integer with the same product.
+    pub fn restricted(radix: u32, allowed: &[char], required: &[char]) -> CandidateDigits {
+        let mut tail: Vec<char> = allowed
+            .iter()
+            .chain(required.iter())
+            .filter(|digit| !['0', '1'].contains(digit) || required.contains(digit))
+            .cloned()
+            .collect();
+        tail.sort();
+        tail.dedup();
+        CandidateDigits {
+            radix,
+            heads: vec![String::new()],
+            tail,
+            required: required.to_vec(),
+        }
+    }
+}
+
 /// An iterator for all search candidates in round n. These follow the pattern
-/// of one optional head digit, followed by n optional tail digits.
+/// of one optional head digit, followed by n tail digits in ascending order.
+/// With the default digits:
 ///
 /// - round 1: 6, 7, ..., 9, 26, 27, ..., 49
 /// - round 2: 66, 67, ..., 99, 266, 267, ..., 499
 #[derive(Debug, Clone)]
-struct Candidates<'a> {
+struct Candidates {
     // Cloneable tails iterator, used to restart tails multiple times
     fresh_tails: CombinationsWithReplacement<char>,
     // Heads that we need to iterate over once
-    heads: std::slice::Iter<'a, &'a str>,
-    current_head: &'a str,
+    heads: std::vec::IntoIter<String>,
+    current_head: String,
     // The current set of tails we are iterating through
     tails: CombinationsWithReplacement<char>,
+    // Digits every candidate must contain
+    required: Vec<char>,
+    // How many more combinations to go through, whether or not they contain required digits,
+    // if limited to a range of ranks
+    remaining: Option<u64>,
 }
 
-impl<'a> Candidates<'a> {
-    pub fn new(n: usize) -> Candidates<'a> {
-        let mut heads = DIGITS_HEAD.iter();
-        let fresh_tails = CombinationsWithReplacement::new(DIGITS_TAIL.to_vec(), n);
+impl Candidates {
+    pub fn new(n: usize, digits: &CandidateDigits) -> Candidates {
+        let mut heads = digits.heads.clone().into_iter();
+        let fresh_tails = CombinationsWithReplacement::new(digits.tail.clone(), n);
         Candidates {
             current_head: heads.next().expect("Heads had no items"),
             heads,
             tails: fresh_tails.clone(),
+            required: digits.required.clone(),
+            remaining: None,
 
             fresh_tails,
         }
     }
+
+    /// Only the candidates in a range of ranks, counting from zero in the order `new`
+    /// yields them, including any without required digits.
+    pub fn ranked(n: usize, digits: &CandidateDigits, ranks: Range<u64>) -> Candidates {
+        let (head_index, tail_rank) = match count_combinations(digits.tail.len(), n) {
+            Some(0) => (digits.heads.len(), 0),
+            Some(tails_count) => (
+                (ranks.start / tails_count) as usize,
+                ranks.start % tails_count,
+            ),
+            // Every rank we can be given is within the first head
+            None => (0, ranks.start),
+        };
+        let mut heads = digits
+            .heads
+            .get(head_index..)
+            .unwrap_or(&[])
+            .to_vec()
+            .into_iter();
+        let fresh_tails = CombinationsWithReplacement::new(digits.tail.clone(), n);
+        let current_head = heads.next();
+        Candidates {
+            remaining: match current_head {
+                Some(_) => Some(ranks.end.saturating_sub(ranks.start)),
+                None => Some(0),
+            },
+            current_head: current_head.unwrap_or_default(),
+            heads,
+            tails: CombinationsWithReplacement::starting_at(digits.tail.clone(), n, tail_rank),
+            required: digits.required.clone(),
+
+            fresh_tails,
+        }
+    }
+}
+
+/// Return the number of candidates in search round n, including any without required digits,
+/// or nothing if there are too many to count.
+pub fn round_size(n: usize, digits: &CandidateDigits) -> Option<u64> {
+    count_combinations(digits.tail.len(), n)?.checked_mul(digits.heads.len() as u64)
 }
 
-impl<'a> Iterator for Candidates<'a> {
+impl Iterator for Candidates {
     type Item = String;
 
     fn next(&mut self) -> Option<String> {
@@ -76,10 +306,17 @@ impl<'a> Iterator for Candidates<'a> {
             match self.tails.next() {
                 // If we have another tail combination, combine with head and return
                 Some(tail_combination) => {
-                    let mut candidate: String = self.current_head.to_string();
+                    match self.remaining.as_mut() {
+                        Some(0) => return None,
+                        Some(remaining) => *remaining -= 1,
+                        None => {}
+                    }
+                    let mut candidate: String = self.current_head.clone();
                     let tail: String = tail_combination.iter().collect();
                     candidate.push_str(&tail);
-                    return Some(candidate);
+                    if self.required.iter().all(|digit| candidate.contains(*digit)) {
+                        return Some(candidate);
+                    }
                 }
                 // If we've exhausted our set of tail combinations
                 // Go to the next head and start tail combinations again
@@ -96,29 +333,42 @@ impl<'a> Iterator for Candidates<'a> {
     }
 }
 
-pub struct SearchRound<'a> {
-    candidates: Candidates<'a>,
+pub struct SearchRound {
+    candidates: Candidates,
+    radix: u32,
     current_max: usize,
 }
 
-impl<'a> SearchRound<'a> {
-    pub fn new(n: usize) -> SearchRound<'a> {
+impl SearchRound {
+    pub fn new(n: usize, digits: &CandidateDigits) -> SearchRound {
+        SearchRound {
+            candidates: Candidates::new(n, digits),
+            radix: digits.radix,
+            current_max: 2,
+        }
+    }
+
+    /// Search only the candidates in a range of ranks within the round.
+    pub fn ranked(n: usize, digits: &CandidateDigits, ranks: Range<u64>) -> SearchRound {
         SearchRound {
-            candidates: Candidates::new(n),
+            candidates: Candidates::ranked(n, digits, ranks),
+            radix: digits.radix,
             current_max: 2,
         }
     }
 }
 
 // Only send messages with potentially higher mp
-impl<'a> Iterator for SearchRound<'a> {
+impl Iterator for SearchRound {
     type Item = SearchResult;
 
     fn next(&mut self) -> Option<SearchResult> {
         loop {
             match self.candidates.next() {
                 Some(candidate) => {
-                    let result = multiplicative_persistence(&candidate, 10);
+                    let candidate_int: BigUint = Num::from_str_radix(&candidate, self.radix)
+                        .expect("Could not convert candidate to BigUint");
+                    let result = multiplicative_persistence_uint(&candidate_int, self.radix);
                     // If we have a potentially better value, report it
                     if result > self.current_max {
                         self.current_max = result;
@@ -144,7 +394,7 @@ mod tests {
     }
 
     // Over 64-bit integer
-    const TOO_LARGE_INT: &'static str =
+    const TOO_LARGE_INT: &str =
         "12346789123467891234678912346789123467891234678912346789123467891234678912346789123467891234678912346789123467891234678912346789";
 
     #[test]
@@ -153,25 +403,25 @@ mod tests {
         assert_eq!(multiplicative_persistence("3", 10), 0);
         assert_eq!(multiplicative_persistence("24", 10), 1);
         assert_eq!(multiplicative_persistence("39", 10), 3);
-        assert_eq!(multiplicative_persistence(&TOO_LARGE_INT, 10), 2);
+        assert_eq!(multiplicative_persistence(TOO_LARGE_INT, 10), 2);
 
         assert_eq!(multiplicative_persistence("24", 13), 1);
     }
 
     #[test]
     fn test_multiply_digits() {
-        assert_eq!(multiply_digits(&big(0), 10), big(0));
-        assert_eq!(multiply_digits(&big(3), 10), big(3));
-        assert_eq!(multiply_digits(&big(24), 10), big(8));
-        assert_eq!(multiply_digits(&big(12345), 10), big(120));
+        assert_eq!(multiply_digits(&big(0), &Standard(10)), big(0));
+        assert_eq!(multiply_digits(&big(3), &Standard(10)), big(3));
+        assert_eq!(multiply_digits(&big(24), &Standard(10)), big(8));
+        assert_eq!(multiply_digits(&big(12345), &Standard(10)), big(120));
 
-        assert_eq!(multiply_digits(&big(12345), 2), big(0));
-        assert_eq!(multiply_digits(&big(24), 13), big(11));
+        assert_eq!(multiply_digits(&big(12345), &Standard(2)), big(0));
+        assert_eq!(multiply_digits(&big(24), &Standard(13)), big(11));
     }
 
     #[test]
     fn test_candidates() {
-        let candidates: Vec<String> = Candidates::new(1).collect();
+        let candidates: Vec<String> = Candidates::new(1, &CandidateDigits::default()).collect();
         let expected: Vec<String> = vec![
             "6", "7", "8", "9", "26", "27", "28", "29", "36", "37", "38", "39", "46", "47", "48",
             "49",
@@ -183,13 +433,179 @@ mod tests {
         assert_eq!(candidates, expected);
     }
 
+    #[test]
+    fn test_candidates_restricted() {
+        let digits = CandidateDigits::restricted(10, &['8', '0', '3', '2'], &[]);
+        assert_eq!(digits.tail, vec!['2', '3', '8']);
+        let candidates: Vec<String> = Candidates::new(2, &digits).collect();
+        assert_eq!(candidates, vec!["22", "23", "28", "33", "38", "88"]);
+
+        let digits = CandidateDigits::restricted(10, &['8', '3', '2'], &['3', '1']);
+        assert_eq!(digits.tail, vec!['1', '2', '3', '8']);
+        let candidates: Vec<String> = Candidates::new(2, &digits).collect();
+        assert_eq!(candidates, vec!["13"]);
+    }
+
     #[test]
     fn test_search_round() {
-        let results: Vec<SearchResult> = SearchRound::new(1).collect();
+        let results: Vec<SearchResult> = SearchRound::new(1, &CandidateDigits::default()).collect();
         let expected = vec![SearchResult {
             candidate: "39".to_owned(),
             multiplicative_persistence: 3,
         }];
         assert_eq!(results, expected);
     }
+
+    #[test]
+    fn test_candidate_digits_for_radix() {
+        assert_eq!(CandidateDigits::for_radix(10), CandidateDigits::default());
+
+        let digits = CandidateDigits::for_radix(12);
+        assert_eq!(digits.heads, vec!["", "2", "3"]);
+        assert_eq!(digits.tail, vec!['4', '5', '6', '7', '8', '9', 'a', 'b']);
+    }
+
+    #[test]
+    fn test_search_round_radix() {
+        for radix in 3..9u32 {
+            // Find the smallest integer with each persistence of 3 or more by brute force
+            let limit = radix.pow(6);
+            let mut expected: Vec<(usize, u32)> = Vec::new();
+            for n in 0..limit {
+                let persistence = multiplicative_persistence_uint(&BigUint::from(n), radix);
+                if persistence > expected.last().map_or(2, |(p, _)| *p) {
+                    expected.push((persistence, n));
+                }
+            }
+
+            let digits = CandidateDigits::for_radix(radix);
+            let mut results: Vec<(usize, u32)> = Vec::new();
+            for n in 1..6 {
+                for result in SearchRound::new(n, &digits) {
+                    let candidate = u32::from_str_radix(&result.candidate, radix).unwrap();
+                    if candidate < limit
+                        && result.multiplicative_persistence > results.last().map_or(2, |(p, _)| *p)
+                    {
+                        results.push((result.multiplicative_persistence, candidate));
+                    }
+                }
+            }
+            assert_eq!(results, expected, "radix {}", radix);
+        }
+    }
+
+    #[test]
+    fn test_candidates_ranked() {
+        let mut digits = CandidateDigits::default();
+        for n in 0..5 {
+            let all: Vec<String> = Candidates::new(n, &digits).collect();
+            let size = round_size(n, &digits).unwrap();
+            assert_eq!(all.len() as u64, size);
+            // Any split into ranges covers the round in order
+            for step in 1..=size + 1 {
+                let mut parts: Vec<String> = Vec::new();
+                let mut start = 0;
+                while start <= size {
+                    parts.extend(Candidates::ranked(n, &digits, start..start + step));
+                    start += step;
+                }
+                assert_eq!(parts, all);
+            }
+        }
+
+        // Required digits are filtered after ranking
+        digits.required = vec!['7'];
+        let ranked: Vec<String> = Candidates::ranked(2, &digits, 0..4).collect();
+        assert_eq!(ranked, vec!["67"]);
+        assert_eq!(Candidates::ranked(2, &digits, 4..7).count(), 3);
+
+        // Rounds too large to count are still searched, and can be ranked
+        let digits = CandidateDigits::for_radix(36);
+        assert_eq!(round_size(40, &digits), None);
+        let first = Candidates::new(40, &digits).next().unwrap();
+        let ranked: Vec<String> = Candidates::ranked(40, &digits, 0..3).collect();
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[0], first);
+    }
+
+    #[test]
+    fn test_digits_large_radix() {
+        let radix = u32::MAX;
+        let a = BigUint::from(u64::MAX);
+        assert_eq!(to_digits(&a, radix), vec![1, 2, 0]);
+        assert_eq!(from_digits(&[1, 2, 0], radix), a);
+        assert_eq!(to_digits(&big(0), 1000), vec![0]);
+        assert_eq!(to_digits(&big(7), 256), vec![7]);
+        assert_eq!(to_digits(&big(1_000_007), 1000), vec![1, 0, 7]);
+    }
+
+    #[test]
+    fn test_notation() {
+        assert_eq!(to_notation(&big(3599), 60), "59:59");
+        assert_eq!(to_notation(&big(3600), 60), "1:0:0");
+        assert_eq!(to_notation(&big(255), 16), "ff");
+        assert_eq!(from_notation("1:0:59", 60), Some(big(3659)));
+        assert_eq!(from_notation("ff", 16), Some(big(255)));
+        assert_eq!(from_notation("1:60", 60), None);
+        assert_eq!(from_notation("1::2", 60), None);
+        assert_eq!(from_notation("g", 16), None);
+    }
+
+    #[test]
+    fn test_multiplicative_persistence_large_radix() {
+        // 59:59 -> 58:1 -> 58
+        assert_eq!(multiplicative_persistence_uint(&big(3599), 60), 2);
+        assert_eq!(multiply_digits(&big(1_000_007), &Standard(1000)), big(0));
+        assert_eq!(multiply_digits(&big(2_003_004), &Standard(1000)), big(24));
+    }
+
+    #[test]
+    fn test_multiplicative_persistence_systems() {
+        use numeral::{Bijective, Factorial, Negabase};
+
+        for n in 0..1000 {
+            assert_eq!(
+                multiplicative_persistence_in(&big(n), &Standard(10)),
+                Some(multiplicative_persistence(&n.to_string(), 10))
+            );
+        }
+
+        // 10 is a single digit in bijective base 10, and 39 has no zeros to differ by
+        assert_eq!(
+            multiplicative_persistence_in(&big(10), &Bijective(10)),
+            Some(0)
+        );
+        assert_eq!(
+            multiplicative_persistence_in(&big(39), &Bijective(10)),
+            Some(3)
+        );
+        // 6 is 11010 in negabinary
+        assert_eq!(
+            multiplicative_persistence_in(&big(6), &Negabase(2)),
+            Some(1)
+        );
+        // 15 is 195 in negadecimal, then 45 is 165, 30 is 170 and 0
+        assert_eq!(
+            multiplicative_persistence_in(&big(15), &Negabase(10)),
+            Some(3)
+        );
+        // 23 is 321 in factorial, then 6 is 100
+        assert_eq!(multiplicative_persistence_in(&big(23), &Factorial), Some(2));
+    }
+
+    #[test]
+    fn test_multiplicative_persistence_loops() {
+        use numeral::Negabase;
+
+        // 26 is 186 in negadecimal, then 48 is 168, which multiplies back to 48
+        assert_eq!(multiplicative_persistence_in(&big(26), &Negabase(10)), None);
+        // 6 is 132 in base -4, which multiplies back to 6
+        assert_eq!(multiplicative_persistence_in(&big(6), &Negabase(4)), None);
+        assert_eq!(multiplicative_persistence_in(&big(44), &Negabase(36)), None);
+        // Integers before the first loop still reach a single digit
+        assert_eq!(
+            multiplicative_persistence_in(&big(25), &Negabase(10)),
+            Some(2)
+        );
+    }
 }
@@ -0,0 +1,137 @@
+use super::range::PersistenceRange;
+
+#[derive(Debug, PartialEq)]
+pub struct MultibaseResult {
+    pub candidate: u64,
+    // Multiplicative persistence in each radix, in the order the radices were given
+    pub multiplicative_persistence: Vec<usize>,
+}
+
+/// How to compare integers scored in several radices.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MultibaseRecord {
+    /// Report integers whose lowest persistence over all radices beats all smaller integers.
+    Minimum,
+    /// Report integers not matched or beaten in every radix by any smaller integer.
+    Pareto,
+}
+
+/// An iterator over records in a range of integers, scored by their multiplicative
+/// persistence in several radices at once.
+///
+/// Only ranges below 2^64 are scanned, one integer at a time; unlike `search`, this does
+/// not reach candidates the length of the known records.
+pub struct MultibaseSearch {
+    ranges: Vec<PersistenceRange>,
+    record: MultibaseRecord,
+    // The best lowest persistence so far
+    current_max: Option<usize>,
+    // Scores of the smallest integers not beaten by any other
+    front: Vec<Vec<usize>>,
+}
+
+impl MultibaseSearch {
+    pub fn new(from: u64, to: u64, radices: &[u32], record: MultibaseRecord) -> MultibaseSearch {
+        MultibaseSearch {
+            ranges: radices
+                .iter()
+                .map(|radix| PersistenceRange::new(from, to, *radix))
+                .collect(),
+            record,
+            current_max: None,
+            front: Vec::new(),
+        }
+    }
+
+    /// Return whether these scores are a new record, updating our state if so.
+    fn is_record(&mut self, scores: &[usize]) -> bool {
+        match self.record {
+            MultibaseRecord::Minimum => {
+                let minimum = scores.iter().cloned().min();
+                if minimum > self.current_max {
+                    self.current_max = minimum;
+                    return true;
+                }
+                false
+            }
+            MultibaseRecord::Pareto => {
+                let dominates = |a: &[usize], b: &[usize]| a.iter().zip(b).all(|(x, y)| x >= y);
+                if self.front.iter().any(|best| dominates(best, scores)) {
+                    return false;
+                }
+                self.front.retain(|best| !dominates(scores, best));
+                self.front.push(scores.to_vec());
+                true
+            }
+        }
+    }
+}
+
+impl Iterator for MultibaseSearch {
+    type Item = MultibaseResult;
+
+    fn next(&mut self) -> Option<MultibaseResult> {
+        loop {
+            let mut candidate = None;
+            let mut scores = Vec::with_capacity(self.ranges.len());
+            for range in self.ranges.iter_mut() {
+                let (n, persistence) = range.next()?;
+                candidate = Some(n);
+                scores.push(persistence);
+            }
+            let candidate = candidate?;
+            if self.is_record(&scores) {
+                return Some(MultibaseResult {
+                    candidate,
+                    multiplicative_persistence: scores,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::multiplicative_persistence;
+
+    #[test]
+    fn test_multibase_minimum() {
+        let candidates: Vec<u64> = MultibaseSearch::new(0, 1000, &[10], MultibaseRecord::Minimum)
+            .map(|result| result.candidate)
+            .collect();
+        assert_eq!(candidates, vec![0, 10, 25, 39, 77, 679]);
+
+        let results: Vec<MultibaseResult> =
+            MultibaseSearch::new(0, 100, &[10, 12], MultibaseRecord::Minimum).collect();
+        assert_eq!(results[1].candidate, 12);
+        assert_eq!(results[1].multiplicative_persistence, vec![1, 1]);
+    }
+
+    #[test]
+    fn test_multibase_pareto() {
+        let radices = [10, 12, 16];
+        let results: Vec<MultibaseResult> =
+            MultibaseSearch::new(0, 5000, &radices, MultibaseRecord::Pareto).collect();
+        assert_eq!(results[0].candidate, 0);
+
+        // No result is matched or beaten in every radix by a smaller integer
+        let all: Vec<Vec<usize>> = (0..5000u64)
+            .map(|n| {
+                radices
+                    .iter()
+                    .map(|radix| multiplicative_persistence(&n.to_string(), *radix))
+                    .collect()
+            })
+            .collect();
+        for result in results.iter() {
+            for smaller in all.iter().take(result.candidate as usize) {
+                assert!(smaller
+                    .iter()
+                    .zip(result.multiplicative_persistence.iter())
+                    .any(|(a, b)| a < b));
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use num_bigint::{BigInt, BigUint};
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use super::to_digits;
+
+/// A way of writing non-negative integers as a sequence of digits.
+pub trait NumeralSystem {
+    /// Return the digits of an integer, most significant first.
+    fn digits(&self, a: &BigUint) -> Vec<u32>;
+
+    /// Return whether an integer is written with at most one digit.
+    fn is_single_digit(&self, a: &BigUint) -> bool {
+        self.digits(a).len() <= 1
+    }
+}
+
+/// The usual positional system, with digits 0 to radix - 1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Standard(pub u32);
+
+impl NumeralSystem for Standard {
+    fn digits(&self, a: &BigUint) -> Vec<u32> {
+        to_digits(a, self.0)
+    }
+
+    fn is_single_digit(&self, a: &BigUint) -> bool {
+        a < &BigUint::from(self.0)
+    }
+}
+
+/// Bijective base-k, with digits 1 to k and no zero. Zero is written with no digits.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bijective(pub u32);
+
+impl NumeralSystem for Bijective {
+    fn digits(&self, a: &BigUint) -> Vec<u32> {
+        let mut digits = Vec::new();
+        let mut rest = a.clone();
+        while !rest.is_zero() {
+            let quotient = (&rest - 1u32) / self.0;
+            let digit = rest - &quotient * self.0;
+            digits.push(digit.to_u32().expect("Digit larger than radix"));
+            rest = quotient;
+        }
+        digits.reverse();
+        digits
+    }
+}
+
+/// Base -k, such as negabinary or negadecimal, with digits 0 to k - 1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Negabase(pub u32);
+
+impl NumeralSystem for Negabase {
+    fn digits(&self, a: &BigUint) -> Vec<u32> {
+        let radix = BigInt::from(self.0);
+        let mut digits = Vec::new();
+        let mut rest = BigInt::from(a.clone());
+        loop {
+            let mut digit = &rest % &radix;
+            if digit.is_negative() {
+                digit += &radix;
+            }
+            rest = (digit.clone() - rest) / &radix;
+            digits.push(digit.to_u32().expect("Digit larger than radix"));
+            if rest.is_zero() {
+                break;
+            }
+        }
+        digits.reverse();
+        digits
+    }
+}
+
+/// The factorial number system, where the digit in place i counts multiples of i! and
+/// is at most i. The 0! place is always zero, so is not written.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Factorial;
+
+impl NumeralSystem for Factorial {
+    fn digits(&self, a: &BigUint) -> Vec<u32> {
+        let mut digits = Vec::new();
+        let mut rest = a.clone();
+        let mut place = 2u32;
+        loop {
+            digits.push((&rest % place).to_u32().expect("Digit larger than place"));
+            rest /= place;
+            place += 1;
+            if rest.is_zero() {
+                break;
+            }
+        }
+        digits.reverse();
+        digits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    /// Evaluate digits against the value of each place, least significant first.
+    fn evaluate(digits: &[u32], places: impl Iterator<Item = i64>) -> i64 {
+        digits
+            .iter()
+            .rev()
+            .zip(places)
+            .map(|(digit, place)| i64::from(*digit) * place)
+            .sum()
+    }
+
+    #[test]
+    fn test_standard() {
+        assert_eq!(Standard(10).digits(&big(679)), vec![6, 7, 9]);
+        assert!(Standard(10).is_single_digit(&big(9)));
+        assert!(!Standard(10).is_single_digit(&big(10)));
+    }
+
+    #[test]
+    fn test_bijective() {
+        assert_eq!(Bijective(10).digits(&big(0)), vec![]);
+        assert_eq!(Bijective(10).digits(&big(10)), vec![10]);
+        assert_eq!(Bijective(10).digits(&big(20)), vec![1, 10]);
+        assert_eq!(Bijective(2).digits(&big(5)), vec![2, 1]);
+        assert_eq!(Bijective(1).digits(&big(3)), vec![1, 1, 1]);
+        for radix in 2..5 {
+            for n in 0..200 {
+                let digits = Bijective(radix).digits(&big(n));
+                assert!(digits.iter().all(|d| *d >= 1 && *d <= radix));
+                let places = (0..).map(|i| i64::from(radix).pow(i));
+                assert_eq!(evaluate(&digits, places), n as i64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_negabase() {
+        assert_eq!(Negabase(2).digits(&big(0)), vec![0]);
+        assert_eq!(Negabase(2).digits(&big(6)), vec![1, 1, 0, 1, 0]);
+        assert_eq!(Negabase(10).digits(&big(15)), vec![1, 9, 5]);
+        for radix in 2..5 {
+            for n in 0..200 {
+                let digits = Negabase(radix).digits(&big(n));
+                assert!(digits.iter().all(|d| *d < radix));
+                let places = (0..).map(|i| (-i64::from(radix)).pow(i));
+                assert_eq!(evaluate(&digits, places), n as i64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_factorial() {
+        assert_eq!(Factorial.digits(&big(0)), vec![0]);
+        assert_eq!(Factorial.digits(&big(2)), vec![1, 0]);
+        assert_eq!(Factorial.digits(&big(463)), vec![3, 4, 1, 0, 1]);
+        for n in 0..800 {
+            let digits = Factorial.digits(&big(n));
+            let places = (1..).scan(1, |factorial, i| {
+                *factorial *= i;
+                Some(*factorial)
+            });
+            assert_eq!(evaluate(&digits, places), n as i64);
+        }
+    }
+}
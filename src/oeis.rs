@@ -0,0 +1,151 @@
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use num_bigint::BigUint;
+use num_traits::Num;
+
+use super::count::persistence_counts;
+use super::range::PersistenceRange;
+use super::{CandidateDigits, SearchRound};
+
+/// A sequence related to multiplicative persistence, in the form OEIS publishes it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OeisSequence {
+    /// The persistence of n, from n = 0 (A031346 in radix 10)
+    Persistence { terms: u64 },
+    /// The smallest integer with persistence n, from n = 0 (A003001 in radix 10), searching
+    /// integers of up to about `rounds` digits
+    Smallest { rounds: usize },
+    /// The number of integers below radix^digits with persistence n, from n = 0
+    Counts { digits: usize },
+}
+
+impl OeisSequence {
+    /// The index of the first term.
+    pub fn offset(&self) -> usize {
+        0
+    }
+
+    /// Return the terms of the sequence in a radix, or why they can't be found in it.
+    pub fn terms(&self, radix: u32) -> Result<Vec<BigUint>, String> {
+        match self {
+            OeisSequence::Persistence { terms } => Ok(PersistenceRange::new(0, *terms, radix)
+                .map(|(_, persistence)| BigUint::from(persistence))
+                .collect()),
+            OeisSequence::Smallest { rounds } => smallest_terms(radix, *rounds),
+            OeisSequence::Counts { digits } => Ok(persistence_counts(*digits, radix)),
+        }
+    }
+}
+
+/// Return the smallest integer with each persistence value, stopping at the first value
+/// not found by searching `rounds` rounds.
+fn smallest_terms(radix: u32, rounds: usize) -> Result<Vec<BigUint>, String> {
+    // Candidates are built from alphanumeric digits
+    if radix > 36 {
+        return Err("Searching for the smallest terms only supports radices up to 36".to_owned());
+    }
+
+    let mut smallest: Vec<Option<BigUint>> = Vec::new();
+    let mut found = |candidate: BigUint, persistence: usize| {
+        if smallest.len() <= persistence {
+            smallest.resize(persistence + 1, None);
+        }
+        if smallest[persistence]
+            .as_ref()
+            .is_none_or(|current| candidate < *current)
+        {
+            smallest[persistence] = Some(candidate);
+        }
+    };
+
+    // Search only reports persistence above two, so check small integers one by one
+    let limit = u64::from(radix)
+        .checked_pow(3)
+        .ok_or("Radix is too large to check small integers")?;
+    for (candidate, persistence) in PersistenceRange::new(0, limit, radix) {
+        found(BigUint::from(candidate), persistence);
+    }
+    let digits = CandidateDigits::for_radix(radix);
+    for n in 0..rounds {
+        for result in SearchRound::new(n, &digits) {
+            let candidate = Num::from_str_radix(&result.candidate, radix)
+                .expect("Could not convert candidate to BigUint");
+            found(candidate, result.multiplicative_persistence);
+        }
+    }
+    Ok(smallest.into_iter().map_while(|term| term).collect())
+}
+
+/// Write terms as an OEIS b-file, one `index term` line each.
+pub fn write_b_file<W: Write, T: Display>(
+    out: &mut W,
+    offset: usize,
+    terms: impl IntoIterator<Item = T>,
+) -> io::Result<()> {
+    for (i, term) in terms.into_iter().enumerate() {
+        writeln!(out, "{} {}", offset + i, term)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(terms: Vec<BigUint>) -> Vec<String> {
+        terms.iter().map(|term| term.to_string()).collect()
+    }
+
+    #[test]
+    fn test_persistence_terms() {
+        let terms = OeisSequence::Persistence { terms: 40 }.terms(10).unwrap();
+        assert_eq!(terms.len(), 40);
+        assert_eq!(terms[25], BigUint::from(2u32));
+        assert_eq!(terms[39], BigUint::from(3u32));
+    }
+
+    #[test]
+    fn test_smallest_terms() {
+        // A003001
+        assert_eq!(
+            strings(OeisSequence::Smallest { rounds: 15 }.terms(10).unwrap()),
+            vec![
+                "0",
+                "10",
+                "25",
+                "39",
+                "77",
+                "679",
+                "6788",
+                "68889",
+                "2677889",
+                "26888999",
+                "3778888999",
+                "277777788888899"
+            ]
+        );
+        assert_eq!(
+            strings(OeisSequence::Smallest { rounds: 4 }.terms(3).unwrap()),
+            vec!["0", "3", "8", "26"]
+        );
+        assert!(OeisSequence::Smallest { rounds: 4 }.terms(40).is_err());
+        assert!(OeisSequence::Smallest { rounds: 4 }
+            .terms(u32::MAX)
+            .is_err());
+    }
+
+    #[test]
+    fn test_counts_terms() {
+        let terms = OeisSequence::Counts { digits: 2 }.terms(10).unwrap();
+        let total: BigUint = terms.iter().sum();
+        assert_eq!(total, BigUint::from(100u32));
+    }
+
+    #[test]
+    fn test_write_b_file() {
+        let mut out = Vec::new();
+        write_b_file(&mut out, 1, vec![5, 8, 13]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "1 5\n2 8\n3 13\n");
+    }
+}
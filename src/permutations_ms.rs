@@ -0,0 +1,94 @@
+/// An iterator for all the distinct permutations of a multiset, in lexicographic order.
+#[derive(Debug, Clone)]
+pub struct MultisetPermutations<T: Copy + Ord> {
+    // The current permutation, starting from the sorted pool
+    current: Vec<T>,
+    // Whether this is the first iteration
+    starting: bool,
+    // Whether we have yielded the last permutation
+    done: bool,
+}
+
+impl<T: Copy + Ord> MultisetPermutations<T> {
+    /// Create a new MultisetPermutations iterator from a `Vec` of `Copy`-able elements,
+    /// which may contain repeats.
+    pub fn new(mut pool: Vec<T>) -> MultisetPermutations<T> {
+        pool.sort();
+        MultisetPermutations {
+            // If the pool is empty, return None immediately
+            done: pool.is_empty(),
+            current: pool,
+            starting: true,
+        }
+    }
+}
+
+impl<T: Copy + Ord> Iterator for MultisetPermutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        if self.starting {
+            self.starting = false;
+            return Some(self.current.clone());
+        }
+
+        // Find the rightmost element smaller than its successor
+        let pivot = match (0..self.current.len() - 1)
+            .rev()
+            .find(|i| self.current[*i] < self.current[i + 1])
+        {
+            Some(pivot) => pivot,
+            // If the permutation is descending, we're done
+            None => {
+                self.done = true;
+                return None;
+            }
+        };
+
+        // Swap it with the rightmost element larger than it, and put the tail back in order
+        let successor = (pivot + 1..self.current.len())
+            .rev()
+            .find(|i| self.current[*i] > self.current[pivot])
+            .expect("Pivot has no successor");
+        self.current.swap(pivot, successor);
+        self.current[pivot + 1..].reverse();
+        Some(self.current.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multiset_permutations() {
+        // Repeated elements
+        let permutations: Vec<Vec<char>> = MultisetPermutations::new(vec!['B', 'A', 'A']).collect();
+        assert_eq!(
+            permutations,
+            vec![
+                vec!['A', 'A', 'B'],
+                vec!['A', 'B', 'A'],
+                vec!['B', 'A', 'A'],
+            ]
+        );
+
+        // Distinct elements
+        let permutations: Vec<Vec<char>> = MultisetPermutations::new(vec!['A', 'B', 'C']).collect();
+        assert_eq!(permutations.len(), 6);
+
+        // Single element
+        let mut permutations = MultisetPermutations::new(vec!['A']);
+        assert_eq!(permutations.next(), Some(vec!['A']));
+        assert_eq!(permutations.next(), None);
+        assert_eq!(permutations.next(), None);
+
+        // Empty pool
+        let empty_char_permutations: Vec<Vec<char>> = vec![];
+        let permutations: Vec<Vec<char>> = MultisetPermutations::new(vec![]).collect();
+        assert_eq!(permutations, empty_char_permutations);
+    }
+}
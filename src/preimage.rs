@@ -0,0 +1,230 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use super::smooth::digit_primes;
+
+/// Return the exponents of `n` over `primes`, or `None` if it has any other prime factor.
+fn factorise(n: &BigUint, primes: &[u32]) -> Option<Vec<usize>> {
+    let mut remainder = n.clone();
+    let exponents = primes
+        .iter()
+        .map(|prime| {
+            let mut exponent = 0;
+            while (&remainder % *prime).is_zero() {
+                remainder /= *prime;
+                exponent += 1;
+            }
+            exponent
+        })
+        .collect();
+    if remainder.is_one() {
+        Some(exponents)
+    } else {
+        None
+    }
+}
+
+/// Return whether every exponent of `digit` is at most the matching one of `exponents`.
+fn divides(digit: &[usize], exponents: &[usize]) -> bool {
+    digit.iter().zip(exponents).all(|(d, e)| d <= e)
+}
+
+/// Pack the factors into digits greedily, taking as many of the largest digit as we can,
+/// then the next largest, and so on, returning the digits in ascending order.
+///
+/// Largest digits first leaves some mix of small factors at the end, which the smaller
+/// digits then take up. In radix 10 this is always the smallest packing: 9s and 8s soak up
+/// threes and twos, and what is left is at most one of 6, 4, 3 or 2 (with 2 and 6 paired
+/// as 26, which beats 34).
+fn greedy_packing(digits: &[(u32, Vec<usize>)], mut exponents: Vec<usize>) -> Vec<u32> {
+    let mut packing = Vec::new();
+    for (digit, digit_exponents) in digits.iter().rev() {
+        while divides(digit_exponents, &exponents) {
+            for (exponent, d) in exponents.iter_mut().zip(digit_exponents) {
+                *exponent -= d;
+            }
+            packing.push(*digit);
+        }
+    }
+    packing.reverse();
+    packing
+}
+
+/// Pack the factors into as few digits as possible, preferring smaller digits first,
+/// returning the digits in ascending order.
+///
+/// Each state is a prime exponent vector dividing the target, laid out in one table so
+/// that removing a digit steps back a fixed distance. We find the fewest digits for every
+/// state, then walk back from the target taking the smallest digit that keeps us on a
+/// shortest path. That digit is the smallest in any shortest packing, so the digits come
+/// out in ascending order.
+fn dynamic_packing(digits: &[(u32, Vec<usize>)], exponents: &[usize]) -> Vec<u32> {
+    let mut strides = Vec::with_capacity(exponents.len());
+    let mut states = 1;
+    for exponent in exponents {
+        strides.push(states);
+        states *= exponent + 1;
+    }
+    let offsets: Vec<usize> = digits
+        .iter()
+        .map(|(_, digit_exponents)| {
+            digit_exponents
+                .iter()
+                .zip(strides.iter())
+                .map(|(d, stride)| d * stride)
+                .sum()
+        })
+        .collect();
+
+    // Every prime below the radix is a digit, so every state can be packed
+    let mut fewest: Vec<u32> = vec![0; states];
+    let mut state_exponents = vec![0; exponents.len()];
+    for state in 1..states {
+        // Step the exponents along with the state, as a mixed radix counter
+        for (exponent, limit) in state_exponents.iter_mut().zip(exponents) {
+            if exponent == limit {
+                *exponent = 0;
+            } else {
+                *exponent += 1;
+                break;
+            }
+        }
+        fewest[state] = digits
+            .iter()
+            .zip(offsets.iter())
+            .filter(|((_, digit_exponents), _)| divides(digit_exponents, &state_exponents))
+            .map(|(_, offset)| fewest[state - offset] + 1)
+            .min()
+            .expect("State has no single digit factor");
+    }
+
+    let mut packing = Vec::new();
+    let mut state = states - 1;
+    let mut state_exponents = exponents.to_vec();
+    while state > 0 {
+        let ((digit, digit_exponents), offset) = digits
+            .iter()
+            .zip(offsets.iter())
+            .find(|((_, digit_exponents), offset)| {
+                divides(digit_exponents, &state_exponents)
+                    && fewest[state - **offset] + 1 == fewest[state]
+            })
+            .expect("Shortest packing has no next digit");
+        for (exponent, d) in state_exponents.iter_mut().zip(digit_exponents) {
+            *exponent -= d;
+        }
+        state -= offset;
+        packing.push(*digit);
+    }
+    packing
+}
+
+/// Return the smallest integer whose digits multiply to `target`, or `None` if there isn't one.
+///
+/// We factorise the target over the single digit primes, then pack the factors into as few
+/// digits as possible, preferring smaller digits first. Packing greedily into the largest
+/// digits is enough in radix 10, but not in general (72 is `3 3 8` greedily in radix 9,
+/// but `2 6 6` is smaller), so other radices search every way of splitting the exponents.
+pub fn smallest_preimage(target: &BigUint, radix: u32) -> Option<BigUint> {
+    // Single digits are their own digit product
+    if target < &BigUint::from(radix) {
+        return Some(target.clone());
+    }
+
+    let primes = digit_primes(radix);
+    let exponents = factorise(target, &primes)?;
+    let digits: Vec<(u32, Vec<usize>)> = (2..radix)
+        .map(|digit| {
+            let digit_exponents = factorise(&BigUint::from(digit), &primes)
+                .expect("Digit has a prime factor above the radix");
+            (digit, digit_exponents)
+        })
+        .collect();
+
+    let packing = if radix == 10 {
+        greedy_packing(&digits, exponents)
+    } else {
+        dynamic_packing(&digits, &exponents)
+    };
+    Some(
+        packing
+            .iter()
+            .fold(BigUint::zero(), |acc, digit| acc * radix + *digit),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    use num_traits::{Pow, ToPrimitive};
+
+    use crate::multiply_digits;
+    use crate::numeral::Standard;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_factorise() {
+        assert_eq!(factorise(&big(360), &[2, 3, 5, 7]), Some(vec![3, 2, 1, 0]));
+        assert_eq!(factorise(&big(1), &[2, 3, 5, 7]), Some(vec![0, 0, 0, 0]));
+        assert_eq!(factorise(&big(22), &[2, 3, 5, 7]), None);
+    }
+
+    #[test]
+    fn test_smallest_preimage() {
+        assert_eq!(smallest_preimage(&big(0), 10), Some(big(0)));
+        assert_eq!(smallest_preimage(&big(7), 10), Some(big(7)));
+        assert_eq!(smallest_preimage(&big(20), 10), Some(big(45)));
+        assert_eq!(smallest_preimage(&big(11), 10), None);
+        assert_eq!(smallest_preimage(&big(26), 10), None);
+        assert_eq!(
+            smallest_preimage(&big(4_996_238_671_872), 10),
+            Some(big(277_777_788_888_899))
+        );
+        assert_eq!(smallest_preimage(&big(72), 9), Some(big(222)));
+    }
+
+    #[test]
+    fn test_smallest_preimage_large() {
+        let power = |base: u64, exponent: u32| Pow::pow(&big(base), exponent);
+        let start = Instant::now();
+
+        // 2^200 is 66 eights with 2^2 over, which joins the spare 3 as 26 rather than 34
+        let target = power(2, 200) * power(3, 151) * power(7, 120);
+        let expected = format!("26{}{}{}", "7".repeat(120), "8".repeat(66), "9".repeat(75));
+        assert_eq!(
+            smallest_preimage(&target, 10).map(|n| n.to_string()),
+            Some(expected)
+        );
+
+        let target = power(2, 30) * power(3, 20) * power(5, 8) * power(7, 8) * power(11, 4);
+        let preimage = smallest_preimage(&target, 12).unwrap();
+        assert_eq!(multiply_digits(&preimage, &Standard(12)), target);
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_smallest_preimage_exhaustive() {
+        // Compare against the first integer to reach each small digit product
+        for radix in 3..17 {
+            let mut first: HashMap<u64, u64> = HashMap::new();
+            for n in 0..u64::from(radix).pow(4) {
+                let product = multiply_digits(&big(n), &Standard(radix));
+                first.entry(product.to_u64().unwrap()).or_insert(n);
+            }
+            for (product, n) in first {
+                if product < u64::from(radix).pow(2) {
+                    assert_eq!(smallest_preimage(&big(product), radix), Some(big(n)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use super::combinations_wr::CombinationsWithReplacement;
+use super::permutations_ms::MultisetPermutations;
+use super::{from_digits, multiplicative_persistence_uint, SearchResult};
+
+/// Witnesses for Miller-Rabin testing. Together these are deterministic for all integers
+/// below 3.3 * 10^24, and are used as fixed probabilistic witnesses beyond that.
+static WITNESSES: &[u32; 13] = &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// Return whether a positive integer is prime, using the Miller-Rabin test.
+pub fn is_prime(n: &BigUint) -> bool {
+    let two = BigUint::from(2u32);
+    if n < &two {
+        return false;
+    }
+    for witness in WITNESSES.iter() {
+        if (n % *witness).is_zero() {
+            return n == &BigUint::from(*witness);
+        }
+    }
+
+    // Write n - 1 as d * 2^s, with d odd
+    let n_minus_one = n - BigUint::one();
+    let mut d = n_minus_one.clone();
+    let mut s = 0;
+    while (&d % 2u32).is_zero() {
+        d >>= 1;
+        s += 1;
+    }
+
+    'witnesses: for witness in WITNESSES.iter() {
+        let mut x = BigUint::from(*witness).modpow(&d, n);
+        if x.is_one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_minus_one {
+                continue 'witnesses;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Return whether some arrangement of a digit multiset of two or more digits could be prime.
+///
+/// Every arrangement has the same digit sum, which is congruent to the integer modulo
+/// `radix - 1`, so any common factor makes them all composite. The last digit must also
+/// share no factor with the radix.
+fn could_be_prime(digits: &[u32], radix: u32) -> bool {
+    let digit_sum = digits
+        .iter()
+        .fold(0, |acc, digit| (acc + digit) % (radix - 1));
+    gcd(digit_sum, radix - 1) == 1 && digits.iter().any(|digit| gcd(*digit, radix) == 1)
+}
+
+/// Return the smallest prime arrangement of any of the digit multisets, if there is one.
+///
+/// Arrangements of each multiset are walked in ascending order, and merged so that we test
+/// all arrangements of the same length in ascending numeric order.
+fn smallest_prime_arrangement(multisets: Vec<Vec<u32>>, radix: u32) -> Option<BigUint> {
+    let mut arrangements: Vec<_> = multisets
+        .into_iter()
+        .map(|multiset| MultisetPermutations::new(multiset).filter(|a| a[0] != 0))
+        .collect();
+    let mut heap = BinaryHeap::new();
+    for (i, multiset_arrangements) in arrangements.iter_mut().enumerate() {
+        if let Some(arrangement) = multiset_arrangements.next() {
+            heap.push(Reverse((arrangement, i)));
+        }
+    }
+    while let Some(Reverse((arrangement, i))) = heap.pop() {
+        let candidate = from_digits(&arrangement, radix);
+        if is_prime(&candidate) {
+            return Some(candidate);
+        }
+        if let Some(next_arrangement) = arrangements[i].next() {
+            heap.push(Reverse((next_arrangement, i)));
+        }
+    }
+    None
+}
+
+/// An iterator over the smallest prime with each multiplicative persistence value,
+/// searching integers of one digit, then two digits, and so on up to `max_length`.
+///
+/// Results are yielded as they are found, which is in ascending order of length, and
+/// ascending order of persistence for the same length.
+pub struct SmallestPrimes {
+    radix: u32,
+    length: usize,
+    max_length: usize,
+    // Persistence values we already have the smallest prime for
+    found: Vec<usize>,
+    // Results from the current length we have not yielded yet
+    results: VecDeque<SearchResult>,
+}
+
+impl SmallestPrimes {
+    pub fn new(max_length: usize, radix: u32) -> SmallestPrimes {
+        SmallestPrimes {
+            radix,
+            length: 1,
+            max_length,
+            found: Vec::new(),
+            results: VecDeque::new(),
+        }
+    }
+
+    /// Find the smallest primes of the current length, for persistence values not yet found.
+    fn search_length(&mut self) {
+        // Every arrangement of a multiset has the same persistence, if it has two or more digits
+        let digits: Vec<u32> = (0..self.radix).collect();
+        let mut multisets: BTreeMap<usize, Vec<Vec<u32>>> = BTreeMap::new();
+        for multiset in CombinationsWithReplacement::new(digits, self.length) {
+            if self.length > 1 && !could_be_prime(&multiset, self.radix) {
+                continue;
+            }
+            let persistence =
+                multiplicative_persistence_uint(&from_digits(&multiset, self.radix), self.radix);
+            if !self.found.contains(&persistence) {
+                multisets.entry(persistence).or_default().push(multiset);
+            }
+        }
+
+        for (persistence, persistence_multisets) in multisets {
+            if let Some(prime) = smallest_prime_arrangement(persistence_multisets, self.radix) {
+                self.found.push(persistence);
+                self.results.push_back(SearchResult {
+                    candidate: prime.to_string(),
+                    multiplicative_persistence: persistence,
+                });
+            }
+        }
+    }
+}
+
+impl Iterator for SmallestPrimes {
+    type Item = SearchResult;
+
+    fn next(&mut self) -> Option<SearchResult> {
+        loop {
+            if let Some(result) = self.results.pop_front() {
+                return Some(result);
+            }
+            if self.length > self.max_length {
+                return None;
+            }
+            self.search_length();
+            self.length += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use num_traits::Num;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_is_prime() {
+        let primes: Vec<u64> = (0..200)
+            .filter(|n| *n > 1 && (2..*n).all(|d| n % d != 0))
+            .collect();
+        for n in 0..200 {
+            assert_eq!(is_prime(&big(n)), primes.contains(&n), "{}", n);
+        }
+
+        // Strong pseudoprimes to the first few bases
+        assert!(!is_prime(&big(3_215_031_751)));
+        assert!(!is_prime(&big(3_825_123_056_546_413_051)));
+
+        // Mersenne primes
+        assert!(is_prime(&big(2_147_483_647)));
+        assert!(is_prime(&big(2_305_843_009_213_693_951)));
+        let m127: BigUint =
+            Num::from_str_radix("170141183460469231731687303715884105727", 10).unwrap();
+        assert!(is_prime(&m127));
+        assert!(!is_prime(&(m127 + big(2))));
+    }
+
+    #[test]
+    fn test_could_be_prime() {
+        assert!(could_be_prime(&[2, 9], 10));
+        assert!(!could_be_prime(&[2, 7], 10));
+        assert!(!could_be_prime(&[2, 4], 10));
+        assert!(!could_be_prime(&[5, 8], 10));
+    }
+
+    #[test]
+    fn test_smallest_primes() {
+        let results: Vec<(usize, String)> = SmallestPrimes::new(8, 10)
+            .map(|result| (result.multiplicative_persistence, result.candidate))
+            .collect();
+        let expected: Vec<(usize, String)> = vec![
+            (0, "2"),
+            (1, "11"),
+            (2, "29"),
+            (3, "47"),
+            (4, "277"),
+            (5, "769"),
+            (6, "8867"),
+            (7, "186889"),
+            (8, "2678789"),
+            (9, "26899889"),
+        ]
+        .into_iter()
+        .map(|(persistence, prime)| (persistence, prime.to_owned()))
+        .collect();
+        assert_eq!(results, expected);
+    }
+}
@@ -0,0 +1,158 @@
+/// Largest number of integers we keep a persistence table for.
+const TABLE_LIMIT: u64 = 1 << 22;
+
+/// Multiply digits of an integer together and return the result.
+fn multiply_digits(mut a: u64, radix: u64) -> u64 {
+    let mut product = 1;
+    loop {
+        product *= a % radix;
+        a /= radix;
+        if a == 0 {
+            return product;
+        }
+    }
+}
+
+/// An iterator over the multiplicative persistence of each integer in a range,
+/// yielding `(integer, persistence)` pairs in order.
+///
+/// Rather than deriving every value from scratch, we reuse earlier work:
+///
+/// - the digit product of `n` is the digit product of `n / radix` times the last digit
+/// - the digit product of `n` is smaller than `n`, so its persistence is usually already
+///   in our table of smaller values
+#[derive(Debug, Clone)]
+pub struct PersistenceRange {
+    radix: u64,
+    next: u64,
+    end: u64,
+    // Integers below this are stored in the tables once reached
+    table_limit: u64,
+    // Digit products and persistence values for every integer below the table length
+    products: Vec<u32>,
+    persistences: Vec<u8>,
+    // The last prefix beyond the table we multiplied out, and its digit product
+    prefix: u64,
+    prefix_product: u64,
+}
+
+impl PersistenceRange {
+    /// Create a new iterator over the integers from `from` (inclusive) to `to` (exclusive).
+    pub fn new(from: u64, to: u64, radix: u32) -> PersistenceRange {
+        PersistenceRange {
+            radix: u64::from(radix),
+            next: from,
+            end: to,
+            table_limit: to.min(TABLE_LIMIT),
+            products: Vec::new(),
+            persistences: Vec::new(),
+            prefix: 0,
+            prefix_product: 1,
+        }
+    }
+
+    /// Fill the tables up to and including `n`. Each new entry only needs entries
+    /// already in the table, as `n / radix` and the digit product of `n` are both smaller.
+    fn extend_table(&mut self, n: u64) {
+        for m in (self.products.len() as u64)..=n {
+            let prefix = m / self.radix;
+            let product = if prefix == 0 {
+                m
+            } else {
+                u64::from(self.products[prefix as usize]) * (m % self.radix)
+            };
+            let persistence = if m < self.radix {
+                0
+            } else {
+                self.persistences[product as usize] + 1
+            };
+            self.products.push(product as u32);
+            self.persistences.push(persistence);
+        }
+    }
+
+    /// Return the digit product of `n`, which is beyond the table.
+    fn product(&mut self, n: u64) -> u64 {
+        let prefix = n / self.radix;
+        if prefix < self.table_limit {
+            self.extend_table(prefix);
+            return u64::from(self.products[prefix as usize]) * (n % self.radix);
+        }
+        if prefix != self.prefix {
+            self.prefix = prefix;
+            self.prefix_product = multiply_digits(prefix, self.radix);
+        }
+        self.prefix_product * (n % self.radix)
+    }
+
+    /// Return the multiplicative persistence of `n`.
+    fn persistence(&mut self, n: u64) -> usize {
+        if n < self.table_limit {
+            self.extend_table(n);
+            return self.persistences[n as usize] as usize;
+        }
+        let product = self.product(n);
+
+        // Follow the chain of digit products until we reach the table
+        let mut derived_int = product;
+        let mut counter = 1;
+        while derived_int >= self.table_limit {
+            derived_int = multiply_digits(derived_int, self.radix);
+            counter += 1;
+        }
+        self.extend_table(derived_int);
+        counter + self.persistences[derived_int as usize] as usize
+    }
+}
+
+impl Iterator for PersistenceRange {
+    type Item = (u64, usize);
+
+    fn next(&mut self) -> Option<(u64, usize)> {
+        if self.next >= self.end {
+            return None;
+        }
+        let n = self.next;
+        self.next += 1;
+        Some((n, self.persistence(n)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::multiplicative_persistence;
+
+    #[test]
+    fn test_multiply_digits() {
+        assert_eq!(multiply_digits(0, 10), 0);
+        assert_eq!(multiply_digits(3, 10), 3);
+        assert_eq!(multiply_digits(12345, 10), 120);
+        assert_eq!(multiply_digits(24, 13), 11);
+    }
+
+    #[test]
+    fn test_persistence_range() {
+        for radix in 2..37 {
+            for (candidate, persistence) in PersistenceRange::new(0, 3000, radix) {
+                assert_eq!(
+                    persistence,
+                    multiplicative_persistence(&candidate.to_string(), radix)
+                );
+            }
+        }
+
+        let results: Vec<(u64, usize)> = PersistenceRange::new(38, 42, 10).collect();
+        assert_eq!(results, vec![(38, 2), (39, 3), (40, 1), (41, 1)]);
+
+        // Beyond the table, the digit products of prefixes are calculated directly
+        let from = 277_777_788_888_800;
+        for (candidate, persistence) in PersistenceRange::new(from, from + 200, 10) {
+            assert_eq!(
+                persistence,
+                multiplicative_persistence(&candidate.to_string(), 10)
+            );
+        }
+    }
+}
@@ -0,0 +1,686 @@
+use num_bigint::BigUint;
+use num_traits::Num;
+
+use super::to_digits;
+
+/// The smallest integer with each multiplicative persistence value in one radix.
+///
+/// These come from an exhaustive search of every candidate up to `searched_digits` digits
+/// long, with the same pruning as `search`, and integers below a few million checked one by
+/// one. Larger records may exist beyond that length. Radix 10 agrees with OEIS A003001.
+#[derive(Debug)]
+pub struct KnownRecords {
+    pub radix: u32,
+    pub searched_digits: usize,
+    // The smallest integer with each persistence value from zero, in decimal
+    pub smallest: &'static [&'static str],
+}
+
+static KNOWN_RECORDS: &[KnownRecords] = &[
+    KnownRecords {
+        radix: 2,
+        searched_digits: 100,
+        smallest: &["0", "2"],
+    },
+    KnownRecords {
+        radix: 3,
+        searched_digits: 100,
+        smallest: &["0", "3", "8", "26"],
+    },
+    KnownRecords {
+        radix: 4,
+        searched_digits: 100,
+        smallest: &["0", "4", "10", "63"],
+    },
+    KnownRecords {
+        radix: 5,
+        searched_digits: 100,
+        smallest: &[
+            "0",
+            "5",
+            "13",
+            "68",
+            "2344",
+            "244140624",
+            "1811981201171874",
+        ],
+    },
+    KnownRecords {
+        radix: 6,
+        searched_digits: 100,
+        smallest: &["0", "6", "15", "23", "172", "3629"],
+    },
+    KnownRecords {
+        radix: 7,
+        searched_digits: 66,
+        smallest: &[
+            "0",
+            "7",
+            "18",
+            "27",
+            "131",
+            "1601",
+            "11262",
+            "686285",
+            "1086400325525346",
+        ],
+    },
+    KnownRecords {
+        radix: 8,
+        searched_digits: 42,
+        smallest: &["0", "8", "20", "31", "174", "1535", "57596799"],
+    },
+    KnownRecords {
+        radix: 9,
+        searched_digits: 30,
+        smallest: &["0", "9", "23", "35", "52", "394", "30536", "1409794"],
+    },
+    KnownRecords {
+        radix: 10,
+        searched_digits: 60,
+        smallest: &[
+            "0",
+            "10",
+            "25",
+            "39",
+            "77",
+            "679",
+            "6788",
+            "68889",
+            "2677889",
+            "26888999",
+            "3778888999",
+            "277777788888899",
+        ],
+    },
+    KnownRecords {
+        radix: 11,
+        searched_digits: 22,
+        smallest: &[
+            "0",
+            "11",
+            "28",
+            "43",
+            "75",
+            "317",
+            "4684",
+            "38200",
+            "757074",
+            "130757439",
+            "199718348047",
+            "20622276737754544313",
+        ],
+    },
+    KnownRecords {
+        radix: 12,
+        searched_digits: 19,
+        smallest: &["0", "12", "30", "46", "83", "1099", "1571", "17902874277"],
+    },
+    KnownRecords {
+        radix: 13,
+        searched_digits: 16,
+        smallest: &[
+            "0",
+            "13",
+            "33",
+            "50",
+            "75",
+            "127",
+            "439",
+            "1494",
+            "7577",
+            "21786",
+            "13274091",
+            "168938314",
+            "494196864368",
+            "38389570874684132",
+        ],
+    },
+    KnownRecords {
+        radix: 14,
+        searched_digits: 15,
+        smallest: &[
+            "0",
+            "14",
+            "35",
+            "54",
+            "81",
+            "135",
+            "667",
+            "2532",
+            "130883",
+            "499407",
+            "397912927",
+            "18693488093783",
+        ],
+    },
+    KnownRecords {
+        radix: 15,
+        searched_digits: 14,
+        smallest: &[
+            "0",
+            "15",
+            "38",
+            "58",
+            "89",
+            "582",
+            "1964",
+            "19526",
+            "596667",
+            "30104309",
+            "140410607143",
+        ],
+    },
+    KnownRecords {
+        radix: 16,
+        searched_digits: 12,
+        smallest: &[
+            "0", "16", "40", "62", "95", "187", "683", "15838", "3644381",
+        ],
+    },
+    KnownRecords {
+        radix: 17,
+        searched_digits: 11,
+        smallest: &[
+            "0",
+            "17",
+            "43",
+            "66",
+            "101",
+            "168",
+            "218",
+            "1101",
+            "2820",
+            "12986",
+            "446163",
+            "5079174",
+            "19710955",
+            "46650378808",
+        ],
+    },
+    KnownRecords {
+        radix: 18,
+        searched_digits: 11,
+        smallest: &[
+            "0",
+            "18",
+            "45",
+            "69",
+            "104",
+            "157",
+            "857",
+            "15820",
+            "61773",
+            "938627",
+            "360129437",
+        ],
+    },
+    KnownRecords {
+        radix: 19,
+        searched_digits: 10,
+        smallest: &[
+            "0",
+            "19",
+            "48",
+            "73",
+            "110",
+            "201",
+            "264",
+            "943",
+            "2752",
+            "5053",
+            "31253",
+            "472548",
+            "1926275",
+            "26556906",
+            "77518543969",
+        ],
+    },
+    KnownRecords {
+        radix: 20,
+        searched_digits: 10,
+        smallest: &[
+            "0", "20", "50", "77", "133", "159", "278", "2674", "5136", "20758", "101536",
+            "429939", "23612759",
+        ],
+    },
+    KnownRecords {
+        radix: 21,
+        searched_digits: 9,
+        smallest: &[
+            "0",
+            "21",
+            "53",
+            "81",
+            "143",
+            "230",
+            "353",
+            "2118",
+            "7452",
+            "53296",
+            "498286",
+            "3083912",
+            "304907819",
+            "247765672579",
+        ],
+    },
+    KnownRecords {
+        radix: 22,
+        searched_digits: 9,
+        smallest: &[
+            "0",
+            "22",
+            "55",
+            "85",
+            "127",
+            "215",
+            "393",
+            "3275",
+            "38631",
+            "135889",
+            "1584592",
+            "297705911",
+            "11527458929",
+        ],
+    },
+    KnownRecords {
+        radix: 23,
+        searched_digits: 8,
+        smallest: &[
+            "0",
+            "23",
+            "58",
+            "89",
+            "133",
+            "180",
+            "227",
+            "412",
+            "2780",
+            "7262",
+            "96806",
+            "217924",
+            "3574934",
+            "100997713",
+            "11661497097",
+        ],
+    },
+    KnownRecords {
+        radix: 24,
+        searched_digits: 9,
+        smallest: &[
+            "0",
+            "24",
+            "60",
+            "92",
+            "119",
+            "185",
+            "382",
+            "3310",
+            "8015",
+            "3144719",
+            "3190632092",
+        ],
+    },
+    KnownRecords {
+        radix: 25,
+        searched_digits: 8,
+        smallest: &[
+            "0",
+            "25",
+            "63",
+            "96",
+            "124",
+            "246",
+            "344",
+            "1593",
+            "2996",
+            "8572",
+            "153124",
+            "1774871",
+            "36311874",
+            "58237874349",
+        ],
+    },
+    KnownRecords {
+        radix: 26,
+        searched_digits: 8,
+        smallest: &[
+            "0",
+            "26",
+            "65",
+            "100",
+            "129",
+            "181",
+            "311",
+            "440",
+            "542",
+            "4029",
+            "12062",
+            "16169",
+            "2090137",
+            "102801347",
+            "20562404246",
+        ],
+    },
+    KnownRecords {
+        radix: 27,
+        searched_digits: 8,
+        smallest: &[
+            "0",
+            "27",
+            "68",
+            "104",
+            "134",
+            "188",
+            "319",
+            "478",
+            "8611",
+            "89341",
+            "1390120",
+            "2101011181",
+        ],
+    },
+    KnownRecords {
+        radix: 28,
+        searched_digits: 7,
+        smallest: &[
+            "0",
+            "28",
+            "70",
+            "108",
+            "139",
+            "195",
+            "307",
+            "2036",
+            "4591",
+            "7699",
+            "81393",
+            "1599247",
+            "79747551",
+            "1322671416",
+        ],
+    },
+    KnownRecords {
+        radix: 29,
+        searched_digits: 7,
+        smallest: &[
+            "0",
+            "29",
+            "73",
+            "112",
+            "144",
+            "198",
+            "283",
+            "456",
+            "575",
+            "692",
+            "2197",
+            "11323",
+            "64608",
+            "237103",
+            "3311799",
+            "54943224",
+            "243962295",
+            "62350256445",
+        ],
+    },
+    KnownRecords {
+        radix: 30,
+        searched_digits: 7,
+        smallest: &[
+            "0",
+            "30",
+            "75",
+            "115",
+            "149",
+            "323",
+            "417",
+            "713",
+            "10586",
+            "17999",
+            "346408",
+            "537238",
+            "62845887",
+            "73959312507",
+        ],
+    },
+    KnownRecords {
+        radix: 31,
+        searched_digits: 7,
+        smallest: &[
+            "0",
+            "31",
+            "78",
+            "119",
+            "154",
+            "239",
+            "422",
+            "738",
+            "2532",
+            "4615",
+            "12268",
+            "136051",
+            "2495498",
+            "77298837",
+            "2282821769",
+            "72451915575",
+        ],
+    },
+    KnownRecords {
+        radix: 32,
+        searched_digits: 7,
+        smallest: &[
+            "0",
+            "32",
+            "80",
+            "123",
+            "159",
+            "255",
+            "381",
+            "633",
+            "2681",
+            "11645",
+            "114493",
+            "8050363",
+            "3604397883",
+        ],
+    },
+    KnownRecords {
+        radix: 33,
+        searched_digits: 7,
+        smallest: &[
+            "0",
+            "33",
+            "83",
+            "127",
+            "164",
+            "259",
+            "485",
+            "658",
+            "2764",
+            "21746",
+            "249347",
+            "4048698",
+            "138206965",
+            "4641292750",
+        ],
+    },
+    KnownRecords {
+        radix: 34,
+        searched_digits: 6,
+        smallest: &[
+            "0",
+            "34",
+            "85",
+            "131",
+            "169",
+            "267",
+            "436",
+            "705",
+            "1016",
+            "5710",
+            "32160",
+            "140751",
+            "3567241",
+            "26325343",
+            "3943284705",
+        ],
+    },
+    KnownRecords {
+        radix: 35,
+        searched_digits: 6,
+        smallest: &[
+            "0",
+            "35",
+            "88",
+            "135",
+            "174",
+            "239",
+            "349",
+            "907",
+            "4547",
+            "23970",
+            "114760",
+            "843882",
+            "20349313",
+            "616756874",
+        ],
+    },
+    KnownRecords {
+        radix: 36,
+        searched_digits: 7,
+        smallest: &[
+            "0",
+            "36",
+            "90",
+            "138",
+            "179",
+            "287",
+            "431",
+            "643",
+            "10151",
+            "19295",
+            "250619",
+            "642821035",
+        ],
+    },
+];
+
+/// Return the known records for a radix from 2 to 36.
+pub fn known_records(radix: u32) -> Option<&'static KnownRecords> {
+    KNOWN_RECORDS.iter().find(|records| records.radix == radix)
+}
+
+impl KnownRecords {
+    /// The largest persistence value found in this radix.
+    pub fn max_persistence(&self) -> usize {
+        self.smallest.len() - 1
+    }
+
+    /// The smallest integer with a persistence value, if one has been found.
+    pub fn smallest(&self, persistence: usize) -> Option<BigUint> {
+        self.smallest
+            .get(persistence)
+            .map(|value| Num::from_str_radix(value, 10).expect("Invalid integer in known records"))
+    }
+
+    /// Describe how an integer with a persistence value contradicts these records, if it
+    /// does. An integer other than the record is only a contradiction if `exhaustive`,
+    /// that is if it should be the smallest integer found with its persistence.
+    pub fn contradiction(
+        &self,
+        candidate: &BigUint,
+        persistence: usize,
+        exhaustive: bool,
+    ) -> Option<String> {
+        match self.smallest(persistence) {
+            Some(smallest) if *candidate < smallest || (exhaustive && *candidate > smallest) => {
+                Some(format!(
+                    "the smallest known integer with persistence {} in radix {} is {}, not {}",
+                    persistence, self.radix, smallest, candidate
+                ))
+            }
+            Some(_) => None,
+            None => {
+                let length = to_digits(candidate, self.radix).len();
+                if length <= self.searched_digits {
+                    Some(format!(
+                        "no integer with persistence {} in radix {} is known up to {} digits, \
+                         but {} has {}",
+                        persistence, self.radix, self.searched_digits, candidate, length
+                    ))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use num_traits::Pow;
+
+    use crate::multiplicative_persistence_uint;
+    use crate::range::PersistenceRange;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_known_records() {
+        for radix in 2..=36 {
+            let records = known_records(radix).unwrap();
+            assert_eq!(records.radix, radix);
+            for persistence in 0..=records.max_persistence() {
+                let smallest = records.smallest(persistence).unwrap();
+                assert_eq!(
+                    multiplicative_persistence_uint(&smallest, radix),
+                    persistence
+                );
+            }
+        }
+        assert!(known_records(1).is_none());
+        assert!(known_records(37).is_none());
+
+        let records = known_records(10).unwrap();
+        assert_eq!(records.max_persistence(), 11);
+        assert_eq!(records.smallest(4), Some(big(77)));
+        assert_eq!(records.smallest(12), None);
+    }
+
+    #[test]
+    fn test_known_records_minimal() {
+        // Against a brute force search of small integers
+        for radix in [3, 7, 10, 16, 36].iter() {
+            let records = known_records(*radix).unwrap();
+            let mut first = Vec::new();
+            for (candidate, persistence) in PersistenceRange::new(0, 100_000, *radix) {
+                if persistence == first.len() {
+                    first.push(big(candidate));
+                }
+            }
+            for (persistence, candidate) in first.iter().enumerate() {
+                assert_eq!(records.smallest(persistence).as_ref(), Some(candidate));
+            }
+        }
+    }
+
+    #[test]
+    fn test_contradiction() {
+        let records = known_records(10).unwrap();
+        assert_eq!(records.contradiction(&big(77), 4, true), None);
+        assert_eq!(records.contradiction(&big(78), 4, false), None);
+        assert!(records.contradiction(&big(78), 4, true).is_some());
+        assert!(records.contradiction(&big(76), 4, false).is_some());
+
+        // Beyond the table, only integers within the searched length contradict it
+        let long: BigUint = Pow::pow(&big(10), 100u32);
+        assert_eq!(records.contradiction(&long, 12, true), None);
+        assert!(records.contradiction(&big(1_000_000), 12, true).is_some());
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeSet;
+
+use num_bigint::BigUint;
+use num_traits::Pow;
+
+use super::multiplicative_persistence_uint;
+
+/// A small seeded pseudo-random generator (SplitMix64), so samples are reproducible.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Return a uniformly random integer below `n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        // Reject the incomplete block at the top of the range, to avoid bias
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
+
+/// How to draw integers of a given length.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SampleMode {
+    /// Uniformly random integers with exactly `length` digits.
+    Integers,
+    /// Uniformly random multisets of `length` digits, scored like any arrangement of them.
+    Multisets,
+}
+
+/// Draw the count of each digit in a random integer or multiset.
+fn draw_counts(length: usize, radix: u32, mode: SampleMode, rng: &mut Rng) -> Vec<usize> {
+    let mut counts = vec![0; radix as usize];
+    match mode {
+        SampleMode::Integers => {
+            if length > 0 {
+                counts[1 + rng.below(u64::from(radix - 1)) as usize] += 1;
+            }
+            for _ in 1..length {
+                counts[rng.below(u64::from(radix)) as usize] += 1;
+            }
+        }
+        SampleMode::Multisets => {
+            // Stars and bars: choose where the radix - 1 bars between digit values go
+            let slots = (length + radix as usize - 1) as u64;
+            let mut bars = BTreeSet::new();
+            while bars.len() < radix as usize - 1 {
+                bars.insert(rng.below(slots) as usize);
+            }
+            let mut previous = 0;
+            for (digit, bar) in bars.iter().chain(Some(&(slots as usize))).enumerate() {
+                counts[digit] = bar - previous;
+                previous = bar + 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Return the multiplicative persistence of any integer with these digit counts.
+fn counts_persistence(counts: &[usize], radix: u32) -> usize {
+    let length: usize = counts.iter().sum();
+    if length < 2 {
+        return 0;
+    }
+    if counts[0] > 0 {
+        return 1;
+    }
+    let product: BigUint = counts
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(digit, count)| Pow::pow(&BigUint::from(digit), *count))
+        .product();
+    1 + multiplicative_persistence_uint(&product, radix)
+}
+
+/// Counts of multiplicative persistence values over random samples of one length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub length: usize,
+    pub samples: usize,
+    // The number of samples with each persistence value
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn proportion(&self, persistence: usize) -> f64 {
+        self.counts.get(persistence).cloned().unwrap_or(0) as f64 / self.samples as f64
+    }
+
+    /// The Wilson score interval for the proportion of a persistence value, where `z`
+    /// is the standard score for the confidence level (1.96 for 95%).
+    pub fn confidence_interval(&self, persistence: usize, z: f64) -> (f64, f64) {
+        let n = self.samples as f64;
+        let p = self.proportion(persistence);
+        let denominator = 1.0 + z * z / n;
+        let centre = (p + z * z / (2.0 * n)) / denominator;
+        let half_width = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+        (
+            (centre - half_width).max(0.0),
+            (centre + half_width).min(1.0),
+        )
+    }
+}
+
+/// Draw `samples` random integers or multisets of `length` digits, and count their
+/// multiplicative persistence values.
+pub fn sample_persistence(
+    length: usize,
+    samples: usize,
+    radix: u32,
+    mode: SampleMode,
+    rng: &mut Rng,
+) -> Histogram {
+    let mut counts = Vec::new();
+    for _ in 0..samples {
+        let persistence = counts_persistence(&draw_counts(length, radix, mode, rng), radix);
+        if counts.len() <= persistence {
+            counts.resize(persistence + 1, 0);
+        }
+        counts[persistence] += 1;
+    }
+    Histogram {
+        length,
+        samples,
+        counts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::multiplicative_persistence;
+
+    #[test]
+    fn test_rng() {
+        // Reference output of SplitMix64
+        let mut rng = Rng::new(0);
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+
+        let draws: Vec<u64> = (0..1000).map(|_| rng.below(7)).collect();
+        assert!(draws.iter().all(|x| *x < 7));
+        assert!((0..7).all(|x| draws.contains(&x)));
+
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+    }
+
+    #[test]
+    fn test_draw_counts() {
+        let mut rng = Rng::new(1);
+        for mode in [SampleMode::Integers, SampleMode::Multisets].iter() {
+            for length in 0..20 {
+                let counts = draw_counts(length, 10, *mode, &mut rng);
+                assert_eq!(counts.iter().sum::<usize>(), length);
+            }
+        }
+        // No leading zeros
+        assert_eq!(draw_counts(1, 10, SampleMode::Integers, &mut rng)[0], 0);
+    }
+
+    #[test]
+    fn test_counts_persistence() {
+        let counts = |digits: &[usize]| {
+            let mut counts = vec![0; 10];
+            for digit in digits {
+                counts[*digit] += 1;
+            }
+            counts
+        };
+        assert_eq!(counts_persistence(&counts(&[7]), 10), 0);
+        assert_eq!(counts_persistence(&counts(&[7, 7]), 10), 4);
+        assert_eq!(counts_persistence(&counts(&[1, 0, 9]), 10), 1);
+        assert_eq!(counts_persistence(&counts(&[2, 5]), 10), 2);
+    }
+
+    #[test]
+    fn test_sample_persistence() {
+        // Two digit integers, against their exact distribution
+        let samples = 20000;
+        let histogram = sample_persistence(2, samples, 10, SampleMode::Integers, &mut Rng::new(7));
+        assert_eq!(histogram.counts.iter().sum::<usize>(), samples);
+        for persistence in 0..histogram.counts.len() {
+            let exact = (10..100)
+                .filter(|n| multiplicative_persistence(&n.to_string(), 10) == persistence)
+                .count() as f64
+                / 90.0;
+            // Loose enough to never fail, tight enough to catch a biased sampler
+            let (low, high) = histogram.confidence_interval(persistence, 4.0);
+            assert!(low <= exact && exact <= high, "persistence {}", persistence);
+        }
+    }
+
+    #[test]
+    fn test_confidence_interval() {
+        let histogram = Histogram {
+            length: 1,
+            samples: 100,
+            counts: vec![0, 50, 50],
+        };
+        let (low, high) = histogram.confidence_interval(0, 1.96);
+        assert_eq!(low, 0.0);
+        assert!(high > 0.0 && high < 0.05);
+        let (low, high) = histogram.confidence_interval(1, 1.96);
+        assert!(low < 0.5 && 0.5 < high);
+        assert!((0.5 - low - (high - 0.5)).abs() < 1e-12);
+    }
+}
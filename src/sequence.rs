@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use super::prime::is_prime;
+use super::{multiplicative_persistence_uint, to_digits};
+
+/// A named integer sequence, indexed from zero.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sequence {
+    /// base^k
+    Powers(u32),
+    /// k!
+    Factorials,
+    /// F(k), starting 0, 1, 1, 2
+    Fibonacci,
+    /// The integer written with k ones in a radix
+    Repunits(u32),
+    /// The product of the first k primes
+    Primorials,
+    /// a(k) = c1 * a(k - 1) + ... + cd * a(k - d), starting from d initial terms
+    Recurrence {
+        coefficients: Vec<BigUint>,
+        initial: Vec<BigUint>,
+    },
+}
+
+impl Sequence {
+    /// A linear recurrence, which needs an initial term for each coefficient.
+    pub fn recurrence(
+        coefficients: Vec<BigUint>,
+        initial: Vec<BigUint>,
+    ) -> Result<Sequence, String> {
+        if initial.len() < coefficients.len() {
+            return Err(format!(
+                "Recurrence with {} coefficients needs at least {} initial terms, not {}",
+                coefficients.len(),
+                coefficients.len(),
+                initial.len()
+            ));
+        }
+        Ok(Sequence::Recurrence {
+            coefficients,
+            initial,
+        })
+    }
+
+    /// Iterate over the terms of the sequence.
+    pub fn terms(&self) -> Terms {
+        Terms {
+            sequence: self.clone(),
+            index: 0,
+            history: VecDeque::new(),
+            prime: BigUint::one(),
+        }
+    }
+
+    /// The number of previous terms needed to find the next.
+    fn depth(&self) -> usize {
+        match self {
+            Sequence::Fibonacci => 2,
+            Sequence::Recurrence {
+                coefficients,
+                initial,
+            } => coefficients.len().max(initial.len()),
+            _ => 1,
+        }
+    }
+}
+
+/// An iterator over the terms of a sequence.
+pub struct Terms {
+    sequence: Sequence,
+    index: usize,
+    // The most recent terms, oldest first
+    history: VecDeque<BigUint>,
+    // The largest prime used so far, for primorials
+    prime: BigUint,
+}
+
+impl Terms {
+    fn next_prime(&mut self) -> BigUint {
+        loop {
+            self.prime += 1u32;
+            if is_prime(&self.prime) {
+                return self.prime.clone();
+            }
+        }
+    }
+}
+
+impl Iterator for Terms {
+    type Item = BigUint;
+
+    fn next(&mut self) -> Option<BigUint> {
+        let index = self.index;
+        let last = self.history.back().cloned();
+        let term = match (&self.sequence, last) {
+            (Sequence::Powers(_), None)
+            | (Sequence::Factorials, None)
+            | (Sequence::Primorials, None) => BigUint::one(),
+            (Sequence::Powers(base), Some(last)) => last * *base,
+            (Sequence::Factorials, Some(last)) => last * index,
+            (Sequence::Primorials, Some(last)) => last * self.next_prime(),
+            (Sequence::Repunits(_), None) => BigUint::zero(),
+            (Sequence::Repunits(radix), Some(last)) => last * *radix + 1u32,
+            (Sequence::Fibonacci, _) if index < 2 => BigUint::from(index),
+            (Sequence::Fibonacci, _) => self.history.iter().sum(),
+            (Sequence::Recurrence { initial, .. }, _) if index < initial.len() => {
+                initial[index].clone()
+            }
+            (Sequence::Recurrence { coefficients, .. }, _) => coefficients
+                .iter()
+                .zip(self.history.iter().rev())
+                .map(|(coefficient, term)| coefficient * term)
+                .sum(),
+        };
+
+        self.history.push_back(term.clone());
+        if self.history.len() > self.sequence.depth() {
+            self.history.pop_front();
+        }
+        self.index += 1;
+        Some(term)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SequenceResult {
+    pub index: usize,
+    // Number of digits in the radix
+    pub length: usize,
+    pub multiplicative_persistence: usize,
+}
+
+/// Iterate over the multiplicative persistence of each term of a sequence.
+pub fn sequence_persistence(
+    sequence: &Sequence,
+    radix: u32,
+) -> impl Iterator<Item = SequenceResult> {
+    sequence
+        .terms()
+        .enumerate()
+        .map(move |(index, term)| SequenceResult {
+            index,
+            length: to_digits(&term, radix).len(),
+            multiplicative_persistence: multiplicative_persistence_uint(&term, radix),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_terms(sequence: Sequence, count: usize) -> Vec<u64> {
+        sequence
+            .terms()
+            .take(count)
+            .map(|term| term.to_string().parse().unwrap())
+            .collect()
+    }
+
+    fn bigs(values: &[u64]) -> Vec<BigUint> {
+        values.iter().map(|value| BigUint::from(*value)).collect()
+    }
+
+    #[test]
+    fn test_terms() {
+        assert_eq!(first_terms(Sequence::Powers(3), 5), vec![1, 3, 9, 27, 81]);
+        assert_eq!(
+            first_terms(Sequence::Factorials, 6),
+            vec![1, 1, 2, 6, 24, 120]
+        );
+        assert_eq!(
+            first_terms(Sequence::Fibonacci, 8),
+            vec![0, 1, 1, 2, 3, 5, 8, 13]
+        );
+        assert_eq!(first_terms(Sequence::Repunits(10), 4), vec![0, 1, 11, 111]);
+        assert_eq!(first_terms(Sequence::Repunits(2), 4), vec![0, 1, 3, 7]);
+        assert_eq!(
+            first_terms(Sequence::Primorials, 6),
+            vec![1, 2, 6, 30, 210, 2310]
+        );
+
+        // Pell numbers
+        let pell = Sequence::Recurrence {
+            coefficients: bigs(&[2, 1]),
+            initial: bigs(&[0, 1]),
+        };
+        assert_eq!(first_terms(pell, 7), vec![0, 1, 2, 5, 12, 29, 70]);
+
+        // Fewer coefficients than initial terms
+        let odd = Sequence::Recurrence {
+            coefficients: bigs(&[1]),
+            initial: bigs(&[3, 5]),
+        };
+        assert_eq!(first_terms(odd, 4), vec![3, 5, 5, 5]);
+    }
+
+    #[test]
+    fn test_recurrence() {
+        assert_eq!(
+            Sequence::recurrence(bigs(&[2, 1]), bigs(&[0, 1])),
+            Ok(Sequence::Recurrence {
+                coefficients: bigs(&[2, 1]),
+                initial: bigs(&[0, 1]),
+            })
+        );
+        assert!(Sequence::recurrence(bigs(&[1]), bigs(&[3, 5])).is_ok());
+        assert_eq!(
+            Sequence::recurrence(bigs(&[1, 1, 1]), bigs(&[0, 1])),
+            Err("Recurrence with 3 coefficients needs at least 3 initial terms, not 2".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_sequence_persistence() {
+        let results: Vec<(usize, usize)> = sequence_persistence(&Sequence::Powers(2), 10)
+            .take(8)
+            .map(|result| (result.length, result.multiplicative_persistence))
+            .collect();
+        // 1, 2, 4, 8, 16, 32, 64, 128
+        assert_eq!(
+            results,
+            vec![
+                (1, 0),
+                (1, 0),
+                (1, 0),
+                (1, 0),
+                (2, 1),
+                (2, 1),
+                (2, 2),
+                (3, 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,885 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use num_bigint::BigUint;
+use num_traits::Num;
+
+use super::explain::explain;
+use super::range::PersistenceRange;
+use super::records::known_records;
+use super::verify::parse_radix;
+use super::visualize::{visualize, VisualizeOptions};
+use super::{
+    multiplicative_persistence_uint, round_size, to_notation, CandidateDigits, SearchRound,
+};
+
+/// Most integers drawn in one visualisation.
+const MAX_VISUALIZE_POINTS: u64 = 1_000_000;
+/// Largest visualisation width or height.
+const MAX_CANVAS_SIZE: i32 = 4096;
+/// Candidates a search job goes through between checks for cancellation.
+const SEARCH_CHUNK_SIZE: u64 = 1 << 16;
+/// Most rounds a search job may reach, counting those it skips with `from_round`.
+const MAX_SEARCH_ROUNDS: usize = 1000;
+/// Most search jobs running at once.
+const MAX_RUNNING_SEARCHES: usize = 4;
+/// Largest request line and headers, in bytes.
+const MAX_REQUEST_HEAD: u64 = 16 * 1024;
+/// Most connections answered at once.
+const MAX_CONNECTIONS: usize = 64;
+/// How long to wait for a client to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Quote a string for JSON.
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// An HTTP request. Parameters are only read from the query string.
+#[derive(Debug, Clone, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+}
+
+impl Request {
+    /// Read a request line and headers, skipping any body. Requests with more than
+    /// `MAX_REQUEST_HEAD` bytes before the body are refused.
+    fn read<R: BufRead>(reader: &mut R) -> io::Result<Request> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Invalid request");
+        let mut head = reader.by_ref().take(MAX_REQUEST_HEAD);
+        let mut read_line = |line: &mut String| {
+            let read = head.read_line(line)?;
+            if read > 0 && !line.ends_with('\n') && head.limit() == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Request too large",
+                ));
+            }
+            Ok(read)
+        };
+        let mut line = String::new();
+        read_line(&mut line)?;
+        let mut fields = line.split_whitespace();
+        let method = fields.next().ok_or_else(invalid)?.to_owned();
+        let target = fields.next().ok_or_else(invalid)?;
+        let (path, query) = match target.find('?') {
+            Some(i) => (&target[..i], &target[i + 1..]),
+            None => (target, ""),
+        };
+        let path = path.to_owned();
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.find('=') {
+                Some(i) => (pair[..i].to_owned(), pair[i + 1..].to_owned()),
+                None => (pair.to_owned(), String::new()),
+            })
+            .collect();
+
+        let mut content_length = 0;
+        loop {
+            line.clear();
+            if read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some(i) = line.find(':') {
+                if line[..i].eq_ignore_ascii_case("content-length") {
+                    content_length = line[i + 1..].trim().parse().map_err(|_| invalid())?;
+                }
+            }
+        }
+        io::copy(&mut reader.take(content_length), &mut io::sink())?;
+
+        Ok(Request {
+            method,
+            path,
+            query,
+        })
+    }
+
+    /// Parse a query parameter, or use a default if it is missing.
+    fn param<T: FromStr>(&self, name: &str, default: Option<T>) -> Result<T, Response> {
+        match (self.query.get(name), default) {
+            (Some(value), _) => value
+                .parse()
+                .map_err(|_| Response::error(400, &format!("Invalid {} '{}'", name, value))),
+            (None, Some(default)) => Ok(default),
+            (None, None) => Err(Response::error(
+                400,
+                &format!("Missing parameter '{}'", name),
+            )),
+        }
+    }
+
+    fn radix(&self) -> Result<u32, Response> {
+        match self.query.get("radix") {
+            Some(value) => parse_radix(value).map_err(|error| Response::error(400, &error)),
+            None => Ok(10),
+        }
+    }
+
+    /// The radix of a search, whose candidates are written with alphanumeric digits.
+    fn search_radix(&self) -> Result<u32, Response> {
+        let radix = self.radix()?;
+        if radix > 36 {
+            return Err(Response::error(400, "Search radix must be from 2 to 36"));
+        }
+        Ok(radix)
+    }
+
+    /// The decimal integer `n`.
+    fn candidate(&self) -> Result<BigUint, Response> {
+        let n: String = self.param("n", None)?;
+        Num::from_str_radix(&n, 10).map_err(|_| Response::error(400, &format!("Invalid n '{}'", n)))
+    }
+
+    fn range(&self) -> Result<(u64, u64), Response> {
+        let from = self.param("from", None)?;
+        let to = self.param("to", None)?;
+        if from > to {
+            return Err(Response::error(400, "from must not be above to"));
+        }
+        Ok((from, to))
+    }
+}
+
+enum Body {
+    Bytes(Vec<u8>),
+    // Sent in chunks as it is produced
+    Lines(Box<dyn Iterator<Item = String> + Send>),
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Body,
+}
+
+/// Writes everything as HTTP chunks.
+struct ChunkedWriter<W: Write>(W);
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() {
+            write!(self.0, "{:x}\r\n", buf.len())?;
+            self.0.write_all(buf)?;
+            self.0.write_all(b"\r\n")?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: Body::Bytes(body.into_bytes()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(status, format!("{{\"error\":{}}}", json_string(message)))
+    }
+
+    fn write_to<W: Write>(self, mut out: W) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        };
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nConnection: close\r\n",
+            self.status, reason, self.content_type
+        )?;
+        match self.body {
+            Body::Bytes(bytes) => {
+                write!(out, "Content-Length: {}\r\n\r\n", bytes.len())?;
+                out.write_all(&bytes)?;
+            }
+            Body::Lines(lines) => {
+                write!(out, "Transfer-Encoding: chunked\r\n\r\n")?;
+                let mut chunks = BufWriter::new(ChunkedWriter(&mut out));
+                for line in lines {
+                    writeln!(chunks, "{}", line)?;
+                }
+                chunks.flush()?;
+                drop(chunks);
+                write!(out, "0\r\n\r\n")?;
+            }
+        }
+        out.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JobStatus {
+    Running,
+    Complete,
+    Cancelled,
+    Failed,
+}
+
+/// A search started through the API, with the records found so far.
+#[derive(Debug, Clone)]
+struct SearchJob {
+    radix: u32,
+    from_round: usize,
+    rounds: usize,
+    rounds_complete: usize,
+    status: JobStatus,
+    records: Vec<(usize, BigUint)>,
+    // Records contradicting the known records
+    warnings: Vec<String>,
+    // Why the search failed, if it did
+    error: Option<String>,
+}
+
+impl SearchJob {
+    fn to_json(&self, id: usize) -> String {
+        let status = match self.status {
+            JobStatus::Running => "running",
+            JobStatus::Complete => "complete",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Failed => "failed",
+        };
+        let records: Vec<String> = self
+            .records
+            .iter()
+            .map(|(persistence, candidate)| {
+                format!(
+                    "{{\"persistence\":{},\"candidate\":\"{}\",\"native\":\"{}\"}}",
+                    persistence,
+                    candidate,
+                    to_notation(candidate, self.radix)
+                )
+            })
+            .collect();
+        let warnings: Vec<String> = self.warnings.iter().map(|w| json_string(w)).collect();
+        let error = match &self.error {
+            Some(error) => format!(",\"error\":{}", json_string(error)),
+            None => String::new(),
+        };
+        format!(
+            "{{\"id\":{},\"status\":\"{}\",\"radix\":{},\"from_round\":{},\"rounds\":{},\
+             \"rounds_complete\":{},\"records\":[{}],\"warnings\":[{}]{}}}",
+            id,
+            status,
+            self.radix,
+            self.from_round,
+            self.rounds,
+            self.rounds_complete,
+            records.join(","),
+            warnings.join(","),
+            error
+        )
+    }
+}
+
+type SearchJobs = Arc<Mutex<Vec<SearchJob>>>;
+
+/// Search like `mpersist search` in one radix, recording progress in the job until it
+/// completes or is cancelled.
+fn run_search(jobs: SearchJobs, index: usize) {
+    let (radix, from_round, rounds) = {
+        let jobs = jobs.lock().unwrap();
+        (
+            jobs[index].radix,
+            jobs[index].from_round,
+            jobs[index].rounds,
+        )
+    };
+    let digits = CandidateDigits::for_radix(radix);
+    let known = known_records(radix);
+    let mut current_max = 2;
+    for n in from_round..(from_round + rounds) {
+        // Rounds too large to count are still ranked, in chunks from the start
+        let size = round_size(n, &digits).unwrap_or(u64::MAX);
+        let mut start = 0;
+        loop {
+            if jobs.lock().unwrap()[index].status != JobStatus::Running {
+                return;
+            }
+            if start >= size {
+                break;
+            }
+            let end = size.min(start.saturating_add(SEARCH_CHUNK_SIZE));
+            let mut records = Vec::new();
+            let mut warnings = Vec::new();
+            for result in SearchRound::ranked(n, &digits, start..end) {
+                if result.multiplicative_persistence <= current_max {
+                    continue;
+                }
+                current_max = result.multiplicative_persistence;
+                let decimal: BigUint = Num::from_str_radix(&result.candidate, radix)
+                    .expect("Could not convert candidate to BigUint");
+                if let Some(message) = known
+                    .and_then(|known| known.contradiction(&decimal, current_max, from_round == 0))
+                {
+                    warnings.push(message);
+                }
+                records.push((current_max, decimal));
+            }
+            let mut jobs = jobs.lock().unwrap();
+            jobs[index].records.extend(records);
+            jobs[index].warnings.extend(warnings);
+            start = end;
+        }
+        jobs.lock().unwrap()[index].rounds_complete += 1;
+    }
+    jobs.lock().unwrap()[index].status = JobStatus::Complete;
+}
+
+/// Run a search job on its own thread. If it fails partway, the job is marked as failed
+/// rather than left running.
+fn spawn_search(jobs: SearchJobs, index: usize) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| run_search(jobs.clone(), index)));
+        if let Err(payload) = result {
+            let message = match (
+                payload.downcast_ref::<&str>(),
+                payload.downcast_ref::<String>(),
+            ) {
+                (Some(message), _) => message.to_string(),
+                (_, Some(message)) => message.clone(),
+                _ => "Search failed".to_owned(),
+            };
+            let mut jobs = jobs.lock().unwrap_or_else(PoisonError::into_inner);
+            jobs[index].status = JobStatus::Failed;
+            jobs[index].error = Some(message);
+        }
+    })
+}
+
+/// Find the job for a path like /search/1.
+fn job_index(path: &str, jobs: &[SearchJob]) -> Result<usize, Response> {
+    let not_found = || Response::error(404, "No such search");
+    let id: usize = path["/search/".len()..].parse().map_err(|_| not_found())?;
+    match id {
+        1..=usize::MAX if id <= jobs.len() => Ok(id - 1),
+        _ => Err(not_found()),
+    }
+}
+
+fn route(request: &Request, jobs: &SearchJobs) -> Result<Response, Response> {
+    let method = request.method.as_str();
+    match (method, request.path.as_str()) {
+        ("GET", "/persistence") => {
+            let radix = request.radix()?;
+            let candidate = request.candidate()?;
+            Ok(Response::json(
+                200,
+                format!(
+                    "{{\"candidate\":\"{}\",\"native\":\"{}\",\"radix\":{},\"persistence\":{}}}",
+                    candidate,
+                    to_notation(&candidate, radix),
+                    radix,
+                    multiplicative_persistence_uint(&candidate, radix)
+                ),
+            ))
+        }
+        ("GET", "/chain") => {
+            let radix = request.radix()?;
+            let candidate = request.candidate()?;
+            let explanation = explain(&candidate, radix);
+            let chain: Vec<String> = explanation
+                .steps
+                .iter()
+                .map(|step| &step.value)
+                .chain(Some(&explanation.final_digit))
+                .map(|value| format!("\"{}\"", value))
+                .collect();
+            Ok(Response::json(
+                200,
+                format!(
+                    "{{\"candidate\":\"{}\",\"radix\":{},\"persistence\":{},\"chain\":[{}]}}",
+                    candidate,
+                    radix,
+                    explanation.multiplicative_persistence(),
+                    chain.join(",")
+                ),
+            ))
+        }
+        ("GET", "/range") => {
+            let radix = request.radix()?;
+            let (from, to) = request.range()?;
+            let lines = PersistenceRange::new(from, to, radix).map(|(candidate, persistence)| {
+                format!(
+                    "{{\"candidate\":\"{}\",\"persistence\":{}}}",
+                    candidate, persistence
+                )
+            });
+            Ok(Response {
+                status: 200,
+                content_type: "application/x-ndjson",
+                body: Body::Lines(Box::new(lines)),
+            })
+        }
+        ("GET", "/visualize.png") => {
+            let radix = request.radix()?;
+            let (from, to) = request.range()?;
+            if to - from > MAX_VISUALIZE_POINTS {
+                return Err(Response::error(
+                    400,
+                    &format!("Range must be at most {} integers", MAX_VISUALIZE_POINTS),
+                ));
+            }
+            let defaults = VisualizeOptions::default();
+            let options = VisualizeOptions {
+                canvas_height: request.param("height", Some(defaults.canvas_height))?,
+                canvas_width: request.param("width", Some(defaults.canvas_width))?,
+                dot_radius: request.param("dot_radius", Some(defaults.dot_radius))?,
+                mp_filter_low: request.param("filter", Some(defaults.mp_filter_low))?,
+                spacing_angular: request
+                    .param("angular_spacing", Some(defaults.spacing_angular))?,
+                spacing_radial: request.param("radial_spacing", Some(defaults.spacing_radial))?,
+            };
+            let size_range = 1..=MAX_CANVAS_SIZE;
+            if !size_range.contains(&options.canvas_width)
+                || !size_range.contains(&options.canvas_height)
+            {
+                return Err(Response::error(
+                    400,
+                    &format!("width and height must be from 1 to {}", MAX_CANVAS_SIZE),
+                ));
+            }
+            let points = PersistenceRange::new(from, to, radix)
+                .map(|(candidate, persistence)| (candidate as f64, persistence as f64));
+            let mut png = Vec::new();
+            visualize(points, &options, &mut png)
+                .map_err(|error| Response::error(500, &error.to_string()))?;
+            Ok(Response {
+                status: 200,
+                content_type: "image/png",
+                body: Body::Bytes(png),
+            })
+        }
+        ("POST", "/search") => {
+            let radix = request.search_radix()?;
+            let from_round = request.param("from_round", Some(0))?;
+            let rounds = request.param("rounds", Some(15))?;
+            if from_round > MAX_SEARCH_ROUNDS || rounds > MAX_SEARCH_ROUNDS - from_round {
+                return Err(Response::error(
+                    400,
+                    &format!("from_round + rounds must be at most {}", MAX_SEARCH_ROUNDS),
+                ));
+            }
+            let job = SearchJob {
+                radix,
+                from_round,
+                rounds,
+                rounds_complete: 0,
+                status: JobStatus::Running,
+                records: Vec::new(),
+                warnings: Vec::new(),
+                error: None,
+            };
+            let (index, body) = {
+                let mut jobs = jobs.lock().unwrap();
+                let running = jobs
+                    .iter()
+                    .filter(|job| job.status == JobStatus::Running)
+                    .count();
+                if running >= MAX_RUNNING_SEARCHES {
+                    return Err(Response::error(
+                        503,
+                        &format!("At most {} searches can run at once", MAX_RUNNING_SEARCHES),
+                    ));
+                }
+                jobs.push(job);
+                let index = jobs.len() - 1;
+                (index, jobs[index].to_json(index + 1))
+            };
+            spawn_search(jobs.clone(), index);
+            Ok(Response::json(202, body))
+        }
+        ("GET", path) | ("DELETE", path) if path.starts_with("/search/") => {
+            let mut jobs = jobs.lock().unwrap();
+            let index = job_index(path, &jobs)?;
+            if method == "DELETE" && jobs[index].status == JobStatus::Running {
+                jobs[index].status = JobStatus::Cancelled;
+            }
+            Ok(Response::json(200, jobs[index].to_json(index + 1)))
+        }
+        (_, "/persistence")
+        | (_, "/chain")
+        | (_, "/range")
+        | (_, "/visualize.png")
+        | (_, "/search") => Err(Response::error(405, "Method not allowed")),
+        (_, path) if path.starts_with("/search/") => {
+            Err(Response::error(405, "Method not allowed"))
+        }
+        _ => Err(Response::error(404, "Not found")),
+    }
+}
+
+fn handle_connection(stream: TcpStream, jobs: &SearchJobs) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let response = match Request::read(&mut BufReader::new(&stream)) {
+        Ok(request) => route(&request, jobs).unwrap_or_else(|response| response),
+        Err(ref error) if error.kind() == io::ErrorKind::InvalidData => {
+            Response::error(400, &error.to_string())
+        }
+        Err(_) => Response::error(400, "Invalid request"),
+    };
+    response.write_to(&stream)
+}
+
+/// An HTTP server answering persistence queries with JSON, one request per connection.
+/// Any radix from 2 up can be given, except to searches, which go up to 36.
+///
+/// - `GET /persistence?n=77&radix=10`: the persistence of a decimal integer
+/// - `GET /chain?n=77`: the chain of digit products down to a single digit
+/// - `GET /range?from=0&to=1000`: persistence of each integer in a range, as JSON lines
+/// - `POST /search?from_round=0&rounds=15`: start a search of up to 1000 rounds in all,
+///   returning its id
+/// - `GET /search/<id>`: a search's status and records so far
+/// - `DELETE /search/<id>`: cancel a search
+/// - `GET /visualize.png?from=0&to=10000`: draw a range as a prime spiral
+pub struct Server {
+    listener: TcpListener,
+    jobs: SearchJobs,
+    // Connections being answered
+    connections: Arc<AtomicUsize>,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(address)?,
+            jobs: Arc::new(Mutex::new(Vec::new())),
+            connections: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Answer requests forever, each connection on its own thread. Connections beyond
+    /// `MAX_CONNECTIONS` are turned away.
+    pub fn serve(self) {
+        for stream in self.listener.incoming().flatten() {
+            if self.connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                let _ = Response::error(503, "Too many connections").write_to(&stream);
+                continue;
+            }
+            self.connections.fetch_add(1, Ordering::SeqCst);
+            let jobs = self.jobs.clone();
+            let connections = self.connections.clone();
+            thread::spawn(move || {
+                let _ = handle_connection(stream, &jobs);
+                connections.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    /// Start a server on a free port, returning its address.
+    fn start() -> SocketAddr {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.serve());
+        address
+    }
+
+    /// Make a request, returning the status and body, with any chunks joined.
+    fn request(address: SocketAddr, method: &str, target: &str) -> (u16, Vec<u8>) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            method, target
+        )
+        .unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let status = line.split(' ').nth(1).unwrap().parse().unwrap();
+        let mut chunked = false;
+        loop {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            chunked |= line.starts_with("Transfer-Encoding: chunked");
+        }
+        let mut body = Vec::new();
+        if !chunked {
+            reader.read_to_end(&mut body).unwrap();
+            return (status, body);
+        }
+        loop {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            let size = usize::from_str_radix(line.trim(), 16).unwrap();
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).unwrap();
+            if size == 0 {
+                return (status, body);
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    }
+
+    fn get(address: SocketAddr, target: &str) -> (u16, String) {
+        let (status, body) = request(address, "GET", target);
+        (status, String::from_utf8(body).unwrap())
+    }
+
+    /// Poll a search until it stops running.
+    fn wait_for_search(address: SocketAddr, id: usize) -> String {
+        for _ in 0..600 {
+            let (_, body) = get(address, &format!("/search/{}", id));
+            if !body.contains("\"status\":\"running\"") {
+                return body;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("Search did not finish");
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a \"b\"\\\n"), "\"a \\\"b\\\"\\\\\\u000a\"");
+    }
+
+    #[test]
+    fn test_persistence() {
+        let address = start();
+        assert_eq!(
+            get(address, "/persistence?n=77"),
+            (
+                200,
+                "{\"candidate\":\"77\",\"native\":\"77\",\"radix\":10,\"persistence\":4}"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            get(address, "/persistence?n=46&radix=12").1,
+            "{\"candidate\":\"46\",\"native\":\"3a\",\"radix\":12,\"persistence\":3}"
+        );
+        assert_eq!(
+            get(address, "/chain?n=77").1,
+            "{\"candidate\":\"77\",\"radix\":10,\"persistence\":4,\
+             \"chain\":[\"77\",\"49\",\"36\",\"18\",\"8\"]}"
+        );
+
+        // Radices above 36 are written with decimal digits separated by colons
+        assert_eq!(
+            get(address, "/persistence?n=3599&radix=60").1,
+            "{\"candidate\":\"3599\",\"native\":\"59:59\",\"radix\":60,\"persistence\":2}"
+        );
+        assert_eq!(get(address, "/chain?n=3599&radix=60").0, 200);
+        assert_eq!(
+            get(address, "/range?from=3599&to=3600&radix=60").1,
+            "{\"candidate\":\"3599\",\"persistence\":2}\n"
+        );
+    }
+
+    #[test]
+    fn test_request_limits() {
+        let read = |request: String| Request::read(&mut Cursor::new(request.into_bytes()));
+        assert!(read("GET /chain?n=77 HTTP/1.1\r\nHost: localhost\r\n\r\n".to_owned()).is_ok());
+        let long_line = format!("GET /chain?n={} HTTP/1.1\r\n\r\n", "7".repeat(20000));
+        assert_eq!(
+            read(long_line).unwrap_err().to_string(),
+            "Request too large"
+        );
+        let many_headers = format!(
+            "GET /chain?n=77 HTTP/1.1\r\n{}\r\n",
+            "X-Padding: padding\r\n".repeat(1000)
+        );
+        assert_eq!(
+            read(many_headers).unwrap_err().to_string(),
+            "Request too large"
+        );
+
+        let address = start();
+        let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(address).unwrap())
+            .collect();
+        let mut line = String::new();
+        BufReader::new(TcpStream::connect(address).unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        assert_eq!(line, "HTTP/1.1 503 Service Unavailable\r\n");
+        drop(idle);
+    }
+
+    #[test]
+    fn test_errors() {
+        let address = start();
+        assert_eq!(
+            get(address, "/persistence?n=x"),
+            (400, "{\"error\":\"Invalid n 'x'\"}".to_owned())
+        );
+        assert_eq!(get(address, "/persistence").0, 400);
+        assert_eq!(
+            get(address, "/persistence?n=7&radix=1"),
+            (400, "{\"error\":\"Invalid radix '1'\"}".to_owned())
+        );
+        assert_eq!(get(address, "/persistence?n=7&radix=4294967296").0, 400);
+        assert_eq!(
+            request(address, "POST", "/search?radix=40"),
+            (
+                400,
+                b"{\"error\":\"Search radix must be from 2 to 36\"}".to_vec()
+            )
+        );
+        assert_eq!(get(address, "/range?from=5&to=1").0, 400);
+        assert_eq!(get(address, "/nothing").0, 404);
+        assert_eq!(get(address, "/search/1").0, 404);
+        assert_eq!(
+            request(
+                address,
+                "POST",
+                "/search?from_round=18446744073709551615&rounds=2"
+            ),
+            (
+                400,
+                b"{\"error\":\"from_round + rounds must be at most 1000\"}".to_vec()
+            )
+        );
+        assert_eq!(request(address, "POST", "/search?rounds=1001").0, 400);
+        assert_eq!(request(address, "DELETE", "/persistence?n=7").0, 405);
+    }
+
+    #[test]
+    fn test_range() {
+        let address = start();
+        let (status, body) = get(address, "/range?from=38&to=41");
+        assert_eq!(status, 200);
+        assert_eq!(
+            body,
+            "{\"candidate\":\"38\",\"persistence\":2}\n\
+             {\"candidate\":\"39\",\"persistence\":3}\n\
+             {\"candidate\":\"40\",\"persistence\":1}\n"
+        );
+        let (_, body) = get(address, "/range?from=0&to=100000");
+        assert_eq!(body.lines().count(), 100000);
+    }
+
+    #[test]
+    fn test_search() {
+        let address = start();
+        let (status, body) = request(address, "POST", "/search?rounds=6");
+        assert_eq!(status, 202);
+        assert!(String::from_utf8(body).unwrap().starts_with("{\"id\":1,"));
+
+        let body = wait_for_search(address, 1);
+        let records: Vec<String> = (3..=7)
+            .map(|persistence| {
+                let smallest = known_records(10).unwrap().smallest(persistence).unwrap();
+                format!(
+                    "{{\"persistence\":{},\"candidate\":\"{}\",\"native\":\"{}\"}}",
+                    persistence, smallest, smallest
+                )
+            })
+            .collect();
+        assert_eq!(
+            body,
+            format!(
+                "{{\"id\":1,\"status\":\"complete\",\"radix\":10,\"from_round\":0,\"rounds\":6,\
+                 \"rounds_complete\":6,\"records\":[{}],\"warnings\":[]}}",
+                records.join(",")
+            )
+        );
+    }
+
+    #[test]
+    fn test_cancel_search() {
+        let address = start();
+        request(address, "POST", "/search?rounds=100");
+        let (status, body) = request(address, "DELETE", "/search/1");
+        assert_eq!(status, 200);
+        assert!(String::from_utf8(body)
+            .unwrap()
+            .contains("\"status\":\"cancelled\""));
+        assert!(wait_for_search(address, 1).contains("\"status\":\"cancelled\""));
+    }
+
+    #[test]
+    fn test_running_search_limit() {
+        let address = start();
+        for _ in 0..MAX_RUNNING_SEARCHES {
+            assert_eq!(request(address, "POST", "/search?rounds=100").0, 202);
+        }
+        assert_eq!(
+            request(address, "POST", "/search?rounds=100"),
+            (
+                503,
+                b"{\"error\":\"At most 4 searches can run at once\"}".to_vec()
+            )
+        );
+        for id in 1..=MAX_RUNNING_SEARCHES {
+            request(address, "DELETE", &format!("/search/{}", id));
+        }
+        assert_eq!(request(address, "POST", "/search?rounds=1").0, 202);
+        wait_for_search(address, MAX_RUNNING_SEARCHES + 1);
+    }
+
+    #[test]
+    fn test_failed_search() {
+        // Search candidates need alphanumeric digits, which the API checks for first
+        let jobs: SearchJobs = Arc::new(Mutex::new(vec![SearchJob {
+            radix: 40,
+            from_round: 0,
+            rounds: 1,
+            rounds_complete: 0,
+            status: JobStatus::Running,
+            records: Vec::new(),
+            warnings: Vec::new(),
+            error: None,
+        }]));
+        spawn_search(jobs.clone(), 0).join().unwrap();
+        let json = jobs.lock().unwrap()[0].to_json(1);
+        assert!(json.contains("\"status\":\"failed\""));
+        assert!(json.contains(",\"error\":\""));
+    }
+
+    #[test]
+    fn test_visualize() {
+        let address = start();
+        let (status, body) = request(address, "GET", "/visualize.png?from=0&to=100&width=50");
+        assert_eq!(status, 200);
+        assert_eq!(&body[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(get(address, "/visualize.png?from=0&to=10&width=0").0, 400);
+    }
+}
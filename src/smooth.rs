@@ -0,0 +1,103 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use super::{multiplicative_persistence_uint, to_digits};
+
+/// Return the primes that are single digits in the given radix.
+pub fn digit_primes(radix: u32) -> Vec<u32> {
+    (2..radix)
+        .filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+        .collect()
+}
+
+/// Return all positive integers up to `bound` (inclusive) with no prime factors larger
+/// than a single digit, in ascending order. Every digit product of a zero-free integer
+/// is one of these, and every one of these is the digit product of some integer.
+pub fn smooth_numbers(bound: &BigUint, radix: u32) -> Vec<BigUint> {
+    if bound.is_zero() {
+        return Vec::new();
+    }
+    let mut numbers = vec![BigUint::one()];
+    for prime in digit_primes(radix) {
+        let mut multiples = Vec::new();
+        for n in numbers.iter() {
+            let mut multiple = n.clone();
+            while &multiple <= bound {
+                multiples.push(multiple.clone());
+                multiple *= prime;
+            }
+        }
+        numbers = multiples;
+    }
+    numbers.sort();
+    numbers
+}
+
+/// Return whether an integer has a zero digit in the given radix.
+fn has_zero_digit(a: &BigUint, radix: u32) -> bool {
+    to_digits(a, radix).contains(&0)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SmoothResult {
+    pub product: BigUint,
+    pub multiplicative_persistence: usize,
+}
+
+/// Search backwards from digit products rather than forwards from candidates.
+///
+/// Any integer whose digits multiply to `product` has a persistence one higher than
+/// `product` itself. Products containing a zero digit are skipped, as they always have
+/// a persistence of one.
+pub fn smooth_search(bound: &BigUint, radix: u32) -> impl Iterator<Item = SmoothResult> {
+    smooth_numbers(bound, radix)
+        .into_iter()
+        .filter(move |product| !has_zero_digit(product, radix))
+        .map(move |product| SmoothResult {
+            multiplicative_persistence: multiplicative_persistence_uint(&product, radix),
+            product,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_digit_primes() {
+        assert_eq!(digit_primes(2), vec![]);
+        assert_eq!(digit_primes(10), vec![2, 3, 5, 7]);
+        assert_eq!(digit_primes(12), vec![2, 3, 5, 7, 11]);
+    }
+
+    #[test]
+    fn test_smooth_numbers() {
+        let expected: Vec<BigUint> = vec![
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 14, 15, 16, 18, 20, 21, 24, 25, 27, 28, 30,
+        ]
+        .into_iter()
+        .map(big)
+        .collect();
+        assert_eq!(smooth_numbers(&big(30), 10), expected);
+        assert_eq!(smooth_numbers(&big(0), 10), vec![]);
+        assert_eq!(smooth_numbers(&big(30), 2), vec![big(1)]);
+    }
+
+    #[test]
+    fn test_smooth_search() {
+        let results: Vec<SmoothResult> = smooth_search(&big(30), 10).collect();
+        assert_eq!(results.len(), 19);
+        assert!(results.iter().all(|result| result.product != big(20)));
+
+        // The digit product of 277777788888899, the smallest integer with persistence 11
+        let record = smooth_search(&big(10_000_000_000_000), 10)
+            .find(|result| result.multiplicative_persistence == 10)
+            .unwrap();
+        assert_eq!(record.product, big(4_996_238_671_872));
+    }
+}
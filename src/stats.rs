@@ -0,0 +1,188 @@
+use std::cmp::max;
+
+/// Return the smaller of two optional integers, ignoring a missing value.
+fn min(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        _ => a.or(b),
+    }
+}
+
+/// Summary statistics of multiplicative persistence values over a collection of integers.
+#[derive(Debug, Clone)]
+pub struct PersistenceStats {
+    pub radix: u32,
+    // Number of integers with each persistence value
+    pub counts: Vec<u64>,
+    // Smallest and largest integer with each persistence value
+    pub first: Vec<Option<u64>>,
+    pub last: Vec<Option<u64>>,
+    // Counts with each persistence value, for integers of each length in digits
+    pub decades: Vec<Vec<u64>>,
+    // Bounds of the decade of the last integer added, to avoid recalculating it
+    decade: (u64, u64, usize),
+}
+
+impl PersistenceStats {
+    pub fn new(radix: u32) -> PersistenceStats {
+        PersistenceStats {
+            radix,
+            counts: Vec::new(),
+            first: Vec::new(),
+            last: Vec::new(),
+            decades: Vec::new(),
+            decade: (0, 0, 0),
+        }
+    }
+
+    /// Return the number of digits of an integer, minus one.
+    fn decade_of(&mut self, candidate: u64) -> usize {
+        let (low, high, decade) = self.decade;
+        if candidate >= low && candidate < high {
+            return decade;
+        }
+        let radix = u64::from(self.radix);
+        let mut low = 0;
+        let mut high = radix;
+        let mut decade = 0;
+        while candidate >= high {
+            low = high;
+            decade += 1;
+            match high.checked_mul(radix) {
+                Some(next) => high = next,
+                None => {
+                    high = u64::MAX;
+                    break;
+                }
+            }
+        }
+        self.decade = (low, high, decade);
+        decade
+    }
+
+    /// Make room for a persistence value.
+    fn grow(&mut self, persistence: usize) {
+        if self.counts.len() <= persistence {
+            self.counts.resize(persistence + 1, 0);
+            self.first.resize(persistence + 1, None);
+            self.last.resize(persistence + 1, None);
+        }
+    }
+
+    pub fn add(&mut self, candidate: u64, persistence: usize) {
+        self.grow(persistence);
+        self.counts[persistence] += 1;
+        self.first[persistence] = min(self.first[persistence], Some(candidate));
+        self.last[persistence] = max(self.last[persistence], Some(candidate));
+
+        let decade = self.decade_of(candidate);
+        if self.decades.len() <= decade {
+            self.decades.resize(decade + 1, Vec::new());
+        }
+        let decade_counts = &mut self.decades[decade];
+        if decade_counts.len() <= persistence {
+            decade_counts.resize(persistence + 1, 0);
+        }
+        decade_counts[persistence] += 1;
+    }
+
+    /// Combine statistics gathered separately, such as over parts of a range.
+    pub fn merge(&mut self, other: &PersistenceStats) {
+        for (persistence, count) in other.counts.iter().enumerate() {
+            self.grow(persistence);
+            self.counts[persistence] += count;
+            self.first[persistence] = min(self.first[persistence], other.first[persistence]);
+            self.last[persistence] = max(self.last[persistence], other.last[persistence]);
+        }
+        if self.decades.len() < other.decades.len() {
+            self.decades.resize(other.decades.len(), Vec::new());
+        }
+        for (decade_counts, counts) in self.decades.iter_mut().zip(other.decades.iter()) {
+            if decade_counts.len() < counts.len() {
+                decade_counts.resize(counts.len(), 0);
+            }
+            for (total, count) in decade_counts.iter_mut().zip(counts.iter()) {
+                *total += count;
+            }
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The mean persistence value, if there are any integers.
+    pub fn mean(&self) -> Option<f64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let sum: f64 = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(persistence, count)| persistence as f64 * *count as f64)
+            .sum();
+        Some(sum / total as f64)
+    }
+
+    /// The smallest integer in a decade, that is with `decade + 1` digits (or zero).
+    pub fn decade_start(&self, decade: usize) -> u64 {
+        if decade == 0 {
+            0
+        } else {
+            u64::from(self.radix).saturating_pow(decade as u32)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::range::PersistenceRange;
+
+    fn range_stats(from: u64, to: u64) -> PersistenceStats {
+        let mut stats = PersistenceStats::new(10);
+        for (candidate, persistence) in PersistenceRange::new(from, to, 10) {
+            stats.add(candidate, persistence);
+        }
+        stats
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = range_stats(0, 100);
+        assert_eq!(stats.total(), 100);
+        assert_eq!(stats.counts[4], 1);
+        assert_eq!(stats.first[4], Some(77));
+        assert_eq!(stats.last[4], Some(77));
+        assert_eq!(stats.first[3], Some(39));
+        assert_eq!(stats.first[0], Some(0));
+        assert_eq!(stats.last[0], Some(9));
+        assert_eq!(stats.decades[0], vec![10]);
+        assert_eq!(stats.decades[1].iter().sum::<u64>(), 90);
+        assert_eq!(stats.decade_start(1), 10);
+
+        let mean = stats.counts[1] + 2 * stats.counts[2] + 3 * stats.counts[3] + 4;
+        assert_eq!(stats.mean(), Some(mean as f64 / 100.0));
+        assert_eq!(PersistenceStats::new(10).mean(), None);
+
+        // Decades at the top of the u64 range
+        let mut stats = PersistenceStats::new(10);
+        stats.add(u64::MAX, 1);
+        assert_eq!(stats.decades.len(), 20);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut merged = range_stats(500, 1000);
+        merged.merge(&range_stats(0, 500));
+        merged.merge(&range_stats(1000, 1000));
+        let expected = range_stats(0, 1000);
+        assert_eq!(merged.counts, expected.counts);
+        assert_eq!(merged.first, expected.first);
+        assert_eq!(merged.last, expected.last);
+        assert_eq!(merged.decades, expected.decades);
+    }
+}
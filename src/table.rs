@@ -0,0 +1,294 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use memmap::Mmap;
+
+/// Identifies a binary persistence table file.
+const MAGIC: &[u8; 4] = b"MPTB";
+/// The format version we read and write.
+const VERSION: u8 = 1;
+/// Length of the fixed header, in bytes.
+pub const HEADER_LENGTH: usize = 28;
+
+/// Describes the contents of a binary persistence table.
+///
+/// The header is laid out little-endian as:
+///
+/// - magic bytes `MPTB`
+/// - format version (`u8`)
+/// - bits per entry (`u8`), either 8 or 4
+/// - two reserved zero bytes
+/// - radix (`u32`)
+/// - first integer in the table (`u64`)
+/// - number of integers in the table (`u64`)
+///
+/// It is followed by one persistence value per integer, in order. Packed tables store
+/// two values per byte, the lower four bits first.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Header {
+    pub radix: u32,
+    pub start: u64,
+    pub count: u64,
+    pub packed: bool,
+}
+
+impl Header {
+    fn bits(&self) -> u8 {
+        if self.packed {
+            4
+        } else {
+            8
+        }
+    }
+
+    /// The largest persistence value an entry can hold.
+    fn max_persistence(&self) -> usize {
+        (1 << self.bits()) - 1
+    }
+
+    /// The length of the table body following the header, in bytes.
+    fn body_length(&self) -> u64 {
+        if self.packed {
+            self.count.div_ceil(2)
+        } else {
+            self.count
+        }
+    }
+
+    fn to_bytes(self) -> [u8; HEADER_LENGTH] {
+        let mut bytes = [0; HEADER_LENGTH];
+        bytes[0..4].copy_from_slice(MAGIC);
+        bytes[4] = VERSION;
+        bytes[5] = self.bits();
+        bytes[8..12].copy_from_slice(&self.radix.to_le_bytes());
+        bytes[12..20].copy_from_slice(&self.start.to_le_bytes());
+        bytes[20..28].copy_from_slice(&self.count.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Header> {
+        if bytes.len() < HEADER_LENGTH || &bytes[0..4] != MAGIC {
+            return Err(invalid_data("Not a persistence table"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid_data("Unsupported persistence table version"));
+        }
+        let packed = match bytes[5] {
+            8 => false,
+            4 => true,
+            _ => return Err(invalid_data("Unsupported persistence table entry size")),
+        };
+        let mut radix = [0; 4];
+        let mut start = [0; 8];
+        let mut count = [0; 8];
+        radix.copy_from_slice(&bytes[8..12]);
+        start.copy_from_slice(&bytes[12..20]);
+        count.copy_from_slice(&bytes[20..28]);
+        Ok(Header {
+            radix: u32::from_le_bytes(radix),
+            start: u64::from_le_bytes(start),
+            count: u64::from_le_bytes(count),
+            packed,
+        })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Streams persistence values into a binary persistence table.
+pub struct TableWriter<W: Write> {
+    writer: W,
+    header: Header,
+    written: u64,
+    // The lower half of a packed byte, waiting for its upper half
+    pending: Option<u8>,
+}
+
+impl<W: Write> TableWriter<W> {
+    /// Create a new writer, immediately writing out the header.
+    pub fn new(mut writer: W, header: Header) -> io::Result<TableWriter<W>> {
+        writer.write_all(&header.to_bytes())?;
+        Ok(TableWriter {
+            writer,
+            header,
+            written: 0,
+            pending: None,
+        })
+    }
+
+    /// Write the persistence value of the next integer in the table.
+    pub fn push(&mut self, persistence: usize) -> io::Result<()> {
+        if self.written >= self.header.count {
+            return Err(invalid_data("More values than declared in the header"));
+        }
+        if persistence > self.header.max_persistence() {
+            return Err(invalid_data("Persistence value too large for the table"));
+        }
+        let value = persistence as u8;
+        self.written += 1;
+        if !self.header.packed {
+            return self.writer.write_all(&[value]);
+        }
+        match self.pending.take() {
+            Some(lower) => self.writer.write_all(&[lower | (value << 4)]),
+            None => {
+                self.pending = Some(value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Flush any half-written byte and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.written != self.header.count {
+            return Err(invalid_data("Fewer values than declared in the header"));
+        }
+        if let Some(lower) = self.pending.take() {
+            self.writer.write_all(&[lower])?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// A binary persistence table, memory-mapped for random access.
+pub struct Table {
+    header: Header,
+    mmap: Mmap,
+}
+
+impl Table {
+    /// Open and memory-map the binary persistence table at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Table> {
+        let file = File::open(path)?;
+        let mmap = unsafe { Mmap::map(&file)? };
+        let header = Header::from_bytes(&mmap)?;
+        if ((mmap.len() - HEADER_LENGTH) as u64) < header.body_length() {
+            return Err(invalid_data("Persistence table is truncated"));
+        }
+        Ok(Table { header, mmap })
+    }
+
+    /// The header describing this table.
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Return the persistence of `candidate`, if it is in the table.
+    pub fn get(&self, candidate: u64) -> Option<usize> {
+        if candidate < self.header.start {
+            return None;
+        }
+        let index = candidate - self.header.start;
+        if index >= self.header.count {
+            return None;
+        }
+        let body = &self.mmap[HEADER_LENGTH..];
+        let value = if self.header.packed {
+            let byte = body[(index / 2) as usize];
+            if index.is_multiple_of(2) {
+                byte & 0x0f
+            } else {
+                byte >> 4
+            }
+        } else {
+            body[index as usize]
+        };
+        Some(value as usize)
+    }
+
+    /// Iterate over every `(integer, persistence)` pair in the table, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, usize)> + '_ {
+        let start = self.header.start;
+        (start..start + self.header.count).map(move |candidate| {
+            let persistence = self.get(candidate).expect("Candidate not in table");
+            (candidate, persistence)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use crate::range::PersistenceRange;
+
+    /// Test helper to write a table for a range of integers to a temporary file.
+    fn write_table(name: &str, from: u64, to: u64, packed: bool) -> Table {
+        let path = env::temp_dir().join(name);
+        let header = Header {
+            radix: 10,
+            start: from,
+            count: to - from,
+            packed,
+        };
+        let mut writer = TableWriter::new(File::create(&path).unwrap(), header).unwrap();
+        for (_, persistence) in PersistenceRange::new(from, to, 10) {
+            writer.push(persistence).unwrap();
+        }
+        writer.finish().unwrap();
+        Table::open(&path).unwrap()
+    }
+
+    #[test]
+    fn test_header() {
+        let header = Header {
+            radix: 13,
+            start: 38,
+            count: 4,
+            packed: true,
+        };
+        assert_eq!(Header::from_bytes(&header.to_bytes()).unwrap(), header);
+        assert!(Header::from_bytes(b"not a table").is_err());
+    }
+
+    #[test]
+    fn test_table() {
+        for packed in [false, true].iter() {
+            let name = format!("mpersist-test-table-{}.mpt", packed);
+            let table = write_table(&name, 38, 143, *packed);
+            assert_eq!(table.header().count, 105);
+            assert_eq!(table.get(37), None);
+            assert_eq!(table.get(38), Some(2));
+            assert_eq!(table.get(39), Some(3));
+            assert_eq!(table.get(77), Some(4));
+            assert_eq!(table.get(142), Some(1));
+            assert_eq!(table.get(143), None);
+
+            let expected: Vec<(u64, usize)> = PersistenceRange::new(38, 143, 10).collect();
+            assert_eq!(table.iter().collect::<Vec<(u64, usize)>>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_table_writer_count() {
+        let header = Header {
+            radix: 10,
+            start: 0,
+            count: 1,
+            packed: false,
+        };
+        let mut writer = TableWriter::new(Vec::new(), header).unwrap();
+        writer.push(0).unwrap();
+        assert!(writer.push(0).is_err());
+
+        let mut writer = TableWriter::new(Vec::new(), Header { count: 2, ..header }).unwrap();
+        assert!(writer.push(16).is_ok());
+        assert!(writer.finish().is_err());
+
+        let mut writer = TableWriter::new(
+            Vec::new(),
+            Header {
+                packed: true,
+                ..header
+            },
+        )
+        .unwrap();
+        assert!(writer.push(16).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use super::combinations_wr::CombinationsWithReplacement;
+
+/// An integer in the digit product graph, with the integers whose digits multiply to it.
+#[derive(Debug, PartialEq)]
+pub struct TreeNode {
+    pub value: BigUint,
+    pub multiplicative_persistence: usize,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    fn new(
+        value: BigUint,
+        multiplicative_persistence: usize,
+        preimages: &HashMap<BigUint, Vec<BigUint>>,
+        max_depth: usize,
+    ) -> TreeNode {
+        let children = match preimages.get(&value) {
+            Some(children) if multiplicative_persistence < max_depth => children
+                .iter()
+                .map(|child| {
+                    TreeNode::new(
+                        child.clone(),
+                        multiplicative_persistence + 1,
+                        preimages,
+                        max_depth,
+                    )
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        TreeNode {
+            value,
+            multiplicative_persistence,
+            children,
+        }
+    }
+
+    fn write_dot(&self, out: &mut String) {
+        writeln!(
+            out,
+            "  \"{}\" [persistence={}];",
+            self.value, self.multiplicative_persistence
+        )
+        .unwrap();
+        for child in self.children.iter() {
+            writeln!(out, "  \"{}\" -> \"{}\";", child.value, self.value).unwrap();
+            child.write_dot(out);
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        write!(
+            out,
+            "{{\"value\":\"{}\",\"persistence\":{},\"children\":",
+            self.value, self.multiplicative_persistence
+        )
+        .unwrap();
+        write_json_list(&self.children, out);
+        out.push('}');
+    }
+}
+
+fn write_json_list(nodes: &[TreeNode], out: &mut String) {
+    out.push('[');
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        node.write_json(out);
+    }
+    out.push(']');
+}
+
+/// Build the tree of integers feeding into each non-zero single digit, where the children
+/// of an integer are those whose digits multiply to it. The depth of each node is its
+/// multiplicative persistence.
+///
+/// Children are the smallest arrangement of each zero-free digit multiset of up to
+/// `max_length` digits, and the tree is expanded no deeper than `max_depth`.
+pub fn preimage_tree(radix: u32, max_length: usize, max_depth: usize) -> Vec<TreeNode> {
+    let digits: Vec<u32> = (1..radix).collect();
+    let mut preimages: HashMap<BigUint, Vec<BigUint>> = HashMap::new();
+    for length in 2..=max_length {
+        for combination in CombinationsWithReplacement::new(digits.clone(), length) {
+            let product: BigUint = combination.iter().map(|d| BigUint::from(*d)).product();
+            let value = combination
+                .iter()
+                .fold(BigUint::zero(), |acc, digit| acc * radix + *digit);
+            preimages.entry(product).or_default().push(value);
+        }
+    }
+    for children in preimages.values_mut() {
+        children.sort();
+    }
+
+    digits
+        .into_iter()
+        .map(|digit| TreeNode::new(BigUint::from(digit), 0, &preimages, max_depth))
+        .collect()
+}
+
+/// Render trees as a Graphviz DOT digraph, with edges from each integer to its digit product.
+pub fn to_dot(roots: &[TreeNode]) -> String {
+    let mut out = String::from("digraph persistence {\n");
+    for root in roots.iter() {
+        root.write_dot(&mut out);
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render trees as a JSON list of nested `value`, `persistence` and `children` objects.
+pub fn to_json(roots: &[TreeNode]) -> String {
+    let mut out = String::new();
+    write_json_list(roots, &mut out);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    #[test]
+    fn test_preimage_tree() {
+        let roots = preimage_tree(10, 2, 2);
+        assert_eq!(roots.len(), 9);
+
+        // 8 <- 18, 24 <- 38, 46
+        let eight = &roots[7];
+        assert_eq!(eight.value, big(8));
+        let children: Vec<&BigUint> = eight.children.iter().map(|c| &c.value).collect();
+        assert_eq!(children, vec![&big(18), &big(24)]);
+        let grandchildren: Vec<&BigUint> = eight.children[1]
+            .children
+            .iter()
+            .map(|c| &c.value)
+            .collect();
+        assert_eq!(grandchildren, vec![&big(38), &big(46)]);
+        assert_eq!(eight.children[1].children[0].multiplicative_persistence, 2);
+
+        // Depth is limited
+        assert!(eight.children[1].children[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_exports() {
+        let roots = preimage_tree(3, 2, 1);
+        assert_eq!(
+            to_dot(&roots),
+            "digraph persistence {\n  \"1\" [persistence=0];\n  \"4\" -> \"1\";\n  \"4\" [persistence=1];\n  \"2\" [persistence=0];\n  \"5\" -> \"2\";\n  \"5\" [persistence=1];\n}\n"
+        );
+        assert_eq!(
+            to_json(&roots),
+            "[{\"value\":\"1\",\"persistence\":0,\"children\":[{\"value\":\"4\",\"persistence\":1,\"children\":[]}]},{\"value\":\"2\",\"persistence\":0,\"children\":[{\"value\":\"5\",\"persistence\":1,\"children\":[]}]}]\n"
+        );
+    }
+}
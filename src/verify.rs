@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+use num_traits::{Num, ToPrimitive, Zero};
+
+use super::from_notation;
+use super::range::PersistenceRange;
+
+/// A claimed multiplicative persistence value, from one line of a results file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claim {
+    pub line: usize,
+    pub radix: u32,
+    pub candidate: BigUint,
+    pub persistence: usize,
+}
+
+/// A claim that did not hold, or a line that could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Return the multiplicative persistence of an integer, independently of the digit
+/// functions used everywhere else.
+pub fn reference_persistence(candidate: &BigUint, radix: u32) -> usize {
+    let radix_int = BigUint::from(radix);
+    let mut value = candidate.clone();
+    let mut persistence = 0;
+    while value >= radix_int {
+        let mut product = BigUint::from(1u32);
+        let mut rest = value;
+        while !rest.is_zero() {
+            product *= &rest % &radix_int;
+            rest /= &radix_int;
+        }
+        value = product;
+        persistence += 1;
+    }
+    persistence
+}
+
+/// Return the value of a field in a flat JSON object, without quotes.
+fn json_field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("\"{}\":", key);
+    let start = line.find(&pattern)? + pattern.len();
+    let rest = line[start..].trim_start();
+    if let Some(quoted) = rest.strip_prefix('"') {
+        quoted.split('"').next()
+    } else {
+        rest.split([',', '}']).next().map(|value| value.trim())
+    }
+}
+
+fn parse_integer(value: &str, name: &str) -> Result<BigUint, String> {
+    Num::from_str_radix(value.trim(), 10).map_err(|_| format!("Invalid {} '{}'", name, value))
+}
+
+fn parse_usize(value: &str, name: &str) -> Result<usize, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {} '{}'", name, value))
+}
+
+/// Parse a radix, which must be at least 2 for integers to have more than one digit.
+pub fn parse_radix(value: &str) -> Result<u32, String> {
+    match value.trim().parse() {
+        Ok(radix) if radix >= 2 => Ok(radix),
+        _ => Err(format!("Invalid radix '{}'", value)),
+    }
+}
+
+/// Reads claims from the output of `list`, `search` or `for`, as text, CSV or JSON lines.
+pub struct ResultsParser {
+    // Whether text lines are search records, giving persistence first
+    records: bool,
+    radix: u32,
+    // Column of the candidate, persistence and optional radix in CSV lines
+    csv_columns: (usize, usize, Option<usize>),
+}
+
+impl ResultsParser {
+    pub fn new(records: bool, radix: u32) -> ResultsParser {
+        ResultsParser {
+            records,
+            radix,
+            csv_columns: (0, 1, None),
+        }
+    }
+
+    /// Parse one line, returning no claim for blank lines and CSV headers.
+    pub fn parse(&mut self, line_number: usize, line: &str) -> Result<Option<Claim>, String> {
+        let line = line.trim();
+        let claim = |radix, candidate, persistence| {
+            Ok(Some(Claim {
+                line: line_number,
+                radix,
+                candidate,
+                persistence,
+            }))
+        };
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        if line.starts_with('{') {
+            let field = |key| json_field(line, key).ok_or(format!("Missing field '{}'", key));
+            let radix = match json_field(line, "radix") {
+                Some(radix) => parse_radix(radix)?,
+                None => self.radix,
+            };
+            return claim(
+                radix,
+                parse_integer(field("candidate")?, "candidate")?,
+                parse_usize(field("persistence")?, "persistence")?,
+            );
+        }
+
+        if line.contains(',') {
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+            if fields.contains(&"candidate") || fields.contains(&"persistence") {
+                let column = |name| fields.iter().position(|field| *field == name);
+                self.csv_columns = (
+                    column("candidate").ok_or("Missing column 'candidate'")?,
+                    column("persistence").ok_or("Missing column 'persistence'")?,
+                    column("radix"),
+                );
+                return Ok(None);
+            }
+            let (candidate, persistence, radix) = self.csv_columns;
+            let field = |i: usize| fields.get(i).cloned().ok_or("Missing column".to_owned());
+            let radix = match radix {
+                Some(i) => parse_radix(field(i)?)?,
+                None => self.radix,
+            };
+            return claim(
+                radix,
+                parse_integer(field(candidate)?, "candidate")?,
+                parse_usize(field(persistence)?, "persistence")?,
+            );
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match (self.records, fields.len()) {
+            (false, 2) => claim(
+                self.radix,
+                parse_integer(fields[0], "candidate")?,
+                parse_usize(fields[1], "persistence")?,
+            ),
+            (true, 2) => claim(
+                self.radix,
+                parse_integer(fields[1], "candidate")?,
+                parse_usize(fields[0], "persistence")?,
+            ),
+            // Records outside radix 10, with the candidate also in native notation
+            (true, 3) | (true, 4) => {
+                let radix = match fields.len() {
+                    4 => parse_radix(fields[0])?,
+                    _ => self.radix,
+                };
+                let fields = &fields[fields.len() - 3..];
+                let candidate = parse_integer(fields[1], "candidate")?;
+                if from_notation(fields[2], radix).as_ref() != Some(&candidate) {
+                    return Err(format!(
+                        "Native notation {} is not {} in radix {}",
+                        fields[2], candidate, radix
+                    ));
+                }
+                claim(radix, candidate, parse_usize(fields[0], "persistence")?)
+            }
+            _ => Err(format!("Unexpected number of fields: {}", fields.len())),
+        }
+    }
+}
+
+/// Check each claimed persistence value. For search records, also check that they
+/// increase in order, and that candidates up to `minimality_limit` are the smallest
+/// integer with their persistence.
+pub fn verify_claims(claims: &[Claim], records: bool, minimality_limit: u64) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let mut fail = |line, message| failures.push(Failure { line, message });
+
+    for claim in claims.iter() {
+        let persistence = reference_persistence(&claim.candidate, claim.radix);
+        if persistence != claim.persistence {
+            fail(
+                claim.line,
+                format!(
+                    "{} has persistence {}, not {}",
+                    claim.candidate, persistence, claim.persistence
+                ),
+            );
+        }
+    }
+    if !records {
+        return failures;
+    }
+
+    let mut previous: HashMap<u32, &Claim> = HashMap::new();
+    for claim in claims.iter() {
+        if let Some(last) = previous.get(&claim.radix) {
+            if claim.persistence <= last.persistence || claim.candidate <= last.candidate {
+                fail(
+                    claim.line,
+                    format!("Record does not improve on line {}", last.line),
+                );
+            }
+        }
+        previous.insert(claim.radix, claim);
+    }
+
+    // Find where each persistence value first occurs, with one scan per radix
+    let mut limits: HashMap<u32, u64> = HashMap::new();
+    for claim in claims.iter() {
+        if let Some(candidate) = claim.candidate.to_u64() {
+            if candidate <= minimality_limit {
+                let limit = limits.entry(claim.radix).or_default();
+                *limit = (*limit).max(candidate + 1);
+            }
+        }
+    }
+    for (radix, limit) in limits {
+        let mut first: HashMap<usize, u64> = HashMap::new();
+        for (candidate, persistence) in PersistenceRange::new(0, limit, radix) {
+            first.entry(persistence).or_insert(candidate);
+        }
+        for claim in claims.iter().filter(|claim| claim.radix == radix) {
+            let candidate = match claim.candidate.to_u64() {
+                Some(candidate) if candidate < limit => candidate,
+                _ => continue,
+            };
+            if let Some(smaller) = first.get(&claim.persistence) {
+                if *smaller < candidate {
+                    fail(
+                        claim.line,
+                        format!(
+                            "{} is not the smallest integer with persistence {}, {} is",
+                            candidate, claim.persistence, smaller
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::multiplicative_persistence;
+
+    /// Test helper to cut down on boilerplate
+    fn big(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn parse_all(records: bool, radix: u32, lines: &[&str]) -> Vec<Result<Option<Claim>, String>> {
+        let mut parser = ResultsParser::new(records, radix);
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| parser.parse(i + 1, line))
+            .collect()
+    }
+
+    fn claim(line: usize, radix: u32, candidate: u64, persistence: usize) -> Claim {
+        Claim {
+            line,
+            radix,
+            candidate: big(candidate),
+            persistence,
+        }
+    }
+
+    #[test]
+    fn test_reference_persistence() {
+        for n in 0..2000u64 {
+            for radix in [2, 10, 16].iter() {
+                assert_eq!(
+                    reference_persistence(&big(n), *radix),
+                    multiplicative_persistence(&n.to_string(), *radix)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let claims = parse_all(
+            false,
+            10,
+            &[
+                "38 3",
+                "",
+                "candidate,persistence",
+                "39,3",
+                "persistence,candidate,radix",
+                "2,63,12",
+                "{\"candidate\":\"77\",\"persistence\":4}",
+                "{\"persistence\": 2, \"candidate\": \"25\", \"radix\": 10}",
+                "77",
+                "x 1",
+            ],
+        );
+        assert_eq!(claims[0], Ok(Some(claim(1, 10, 38, 3))));
+        assert_eq!(claims[1], Ok(None));
+        assert_eq!(claims[2], Ok(None));
+        assert_eq!(claims[3], Ok(Some(claim(4, 10, 39, 3))));
+        assert_eq!(claims[5], Ok(Some(claim(6, 12, 63, 2))));
+        assert_eq!(claims[6], Ok(Some(claim(7, 10, 77, 4))));
+        assert_eq!(claims[7], Ok(Some(claim(8, 10, 25, 2))));
+        assert!(claims[8].is_err());
+        assert_eq!(claims[9], Err("Invalid candidate 'x'".to_owned()));
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let claims = parse_all(true, 12, &["3 39", "3 46 3a", "11 4 75 69", "3 46 3b"]);
+        assert_eq!(claims[0], Ok(Some(claim(1, 12, 39, 3))));
+        assert_eq!(claims[1], Ok(Some(claim(2, 12, 46, 3))));
+        assert_eq!(claims[2], Ok(Some(claim(3, 11, 75, 4))));
+        assert!(claims[3].is_err());
+    }
+
+    #[test]
+    fn test_parse_radix() {
+        let claims = parse_all(
+            false,
+            10,
+            &[
+                "candidate,persistence,radix",
+                "25,2,0",
+                "25,2,1",
+                "25,2,4294967306",
+                "25,2,36",
+                "{\"candidate\":\"25\",\"persistence\":2,\"radix\":1}",
+            ],
+        );
+        assert_eq!(claims[1], Err("Invalid radix '0'".to_owned()));
+        assert_eq!(claims[2], Err("Invalid radix '1'".to_owned()));
+        assert_eq!(claims[3], Err("Invalid radix '4294967306'".to_owned()));
+        assert_eq!(claims[4], Ok(Some(claim(5, 36, 25, 2))));
+        assert!(claims[5].is_err());
+        let claims = parse_all(true, 10, &["0 2 25 25"]);
+        assert_eq!(claims[0], Err("Invalid radix '0'".to_owned()));
+    }
+
+    #[test]
+    fn test_verify_claims() {
+        let claims = vec![
+            claim(1, 10, 39, 3),
+            claim(2, 10, 77, 4),
+            claim(3, 10, 679, 5),
+        ];
+        assert!(verify_claims(&claims, true, 1000).is_empty());
+
+        // Wrong persistence
+        let failures = verify_claims(&[claim(1, 10, 39, 4)], false, 1000);
+        assert_eq!(failures[0].line, 1);
+        assert_eq!(failures[0].message, "39 has persistence 3, not 4");
+
+        // Not increasing
+        let claims = vec![claim(1, 10, 77, 4), claim(2, 10, 39, 3)];
+        let failures = verify_claims(&claims, true, 1000);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].line, 2);
+
+        // Not minimal, unless beyond the limit
+        let claims = vec![claim(1, 10, 49, 3)];
+        assert_eq!(verify_claims(&claims, true, 1000)[0].line, 1);
+        assert!(verify_claims(&claims, true, 10).is_empty());
+
+        // Radices are checked separately
+        let claims = vec![claim(1, 10, 39, 3), claim(2, 12, 46, 3)];
+        assert!(verify_claims(&claims, true, 1000).is_empty());
+    }
+}
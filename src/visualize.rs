@@ -0,0 +1,89 @@
+use std::f64::consts::PI;
+use std::io::{self, Write};
+
+use cairo::{Context, Format, ImageSurface};
+
+/// How to draw mp values as a prime spiral.
+#[derive(Debug, Copy, Clone)]
+pub struct VisualizeOptions {
+    pub canvas_height: i32,
+    pub canvas_width: i32,
+    // Multiplier for the radius of each dot
+    pub dot_radius: f64,
+    // Dots for mp values up to this are not drawn
+    pub mp_filter_low: f64,
+    pub spacing_angular: f64,
+    pub spacing_radial: f64,
+}
+
+impl Default for VisualizeOptions {
+    fn default() -> VisualizeOptions {
+        VisualizeOptions {
+            canvas_height: 600,
+            canvas_width: 600,
+            dot_radius: 1.0,
+            mp_filter_low: 0.0,
+            spacing_angular: 1.0,
+            spacing_radial: 5.0,
+        }
+    }
+}
+
+/// Draw `(integer, persistence)` pairs as a prime spiral, writing a PNG image.
+pub fn visualize<I, W>(points: I, options: &VisualizeOptions, out: &mut W) -> io::Result<()>
+where
+    I: Iterator<Item = (f64, f64)>,
+    W: Write,
+{
+    let VisualizeOptions {
+        canvas_height,
+        canvas_width,
+        mp_filter_low,
+        dot_radius,
+        spacing_angular,
+        spacing_radial,
+    } = options;
+    let surface = ImageSurface::create(Format::ARgb32, *canvas_width, *canvas_height)
+        .map_err(|status| io::Error::other(format!("{:?}", status)))?;
+    let context = Context::new(&surface);
+
+    // paint canvas white
+    context.set_source_rgb(1.0, 1.0, 1.0);
+    context.paint();
+    context.set_source_rgb(0.0, 0.0, 0.0);
+
+    // draw line chart
+    for (candidate, mut mp) in points {
+        if mp <= *mp_filter_low {
+            mp = 0.0;
+        }
+
+        let n = candidate * spacing_angular;
+        let r = n.sqrt();
+        let t = 2.0 * PI * r;
+        let x = (*canvas_width as f64 * 0.5) + (r * t.cos()) * spacing_radial;
+        let y = (*canvas_height as f64 * 0.5) - (r * t.sin()) * spacing_radial;
+        let s = mp * dot_radius;
+        context.move_to(x, y);
+        context.arc(x, y, s, 0.0, 2.0 * PI);
+        context.fill();
+    }
+    context.stroke();
+
+    surface
+        .write_to_png(out)
+        .map_err(|error| io::Error::other(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visualize() {
+        let mut out = Vec::new();
+        let points = (0..100).map(|n| (n as f64, (n % 4) as f64));
+        visualize(points, &VisualizeOptions::default(), &mut out).unwrap();
+        assert_eq!(&out[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}